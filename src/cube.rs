@@ -8,12 +8,14 @@
 //! uniquely determines the state of the cube.
 
 use std::{
+    collections::HashSet,
     fmt::Display,
     ops::{Deref, DerefMut},
 };
 
 use strum_macros::EnumString;
 
+use crate::pattern::Pattern;
 use crate::CycleType;
 
 /// An enum for the faces of the Rubik's Cube.
@@ -145,6 +147,84 @@ impl Display for MoveSequence {
     }
 }
 
+/// The textual conventions a `MoveSequence` can be written in.
+///
+/// Every notation can be read back with `parser::parse_scramble_with`.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum Notation {
+    /// Standard Singmaster notation, e.g. `R U' F2`. This is what
+    /// `Display` produces.
+    #[default]
+    Singmaster,
+    /// SiGN notation. Outer face turns are spelled exactly as in Singmaster.
+    Sign,
+    /// Clockwise quarter turn amounts, e.g. `R1 U3 F2`.
+    Numeric,
+    /// Singmaster notation without separators, e.g. `RU'F2`.
+    Compact,
+    /// Move names as used by twsearch and ksolve definition files, e.g.
+    /// `R U' F2`. The amount forms `R1`, `R3` and `R2'` are also accepted
+    /// when parsing.
+    Twsearch,
+}
+
+impl Notation {
+    fn suffix(&self, dir: Direction) -> &'static str {
+        match (self, dir) {
+            (Notation::Numeric, Direction::Normal) => "1",
+            (Notation::Numeric, Direction::Prime) => "3",
+            (_, Direction::Normal) => "",
+            (_, Direction::Prime) => "'",
+            (_, Direction::Double) => "2",
+        }
+    }
+
+    fn separator(&self) -> &'static str {
+        match self {
+            Notation::Compact => "",
+            _ => " ",
+        }
+    }
+}
+
+/// A helper struct for writing a `MoveSequence` in a given `Notation`,
+/// returned by `MoveSequence::display`.
+pub struct NotatedSequence<'a> {
+    sequence: &'a MoveSequence,
+    notation: Notation,
+}
+
+impl Display for NotatedSequence<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, m) in self.sequence.iter().enumerate() {
+            if i != 0 {
+                write!(f, "{}", self.notation.separator())?;
+            }
+            write!(f, "{}{}", m.basemove, self.notation.suffix(m.dir))?;
+        }
+        Ok(())
+    }
+}
+
+impl MoveSequence {
+    /// Returns an object that formats the sequence in the given notation.
+    ///
+    /// ```
+    /// use rusty_rubik::cube::*;
+    /// use rusty_rubik::cube_move;
+    ///
+    /// let seq = MoveSequence::from(vec![cube_move!(R, Normal), cube_move!(U, Prime)]);
+    /// assert_eq!(seq.display(Notation::Numeric).to_string(), "R1 U3");
+    /// assert_eq!(seq.display(Notation::Compact).to_string(), "RU'");
+    /// ```
+    pub fn display(&self, notation: Notation) -> NotatedSequence<'_> {
+        NotatedSequence {
+            sequence: self,
+            notation,
+        }
+    }
+}
+
 impl Deref for MoveSequence {
     type Target = Vec<MoveInstance>;
 
@@ -247,21 +327,6 @@ fn get_antipode(token: BaseMoveToken) -> BaseMoveToken {
     }
 }
 
-// bitvector: [UDLRFB], 0 means it's allowed
-pub(crate) fn get_allowed_post_moves(prev_bv: u8, last_move: Option<BaseMoveToken>) -> u8 {
-    if let Some(lm) = last_move {
-        let antipode = get_antipode(lm);
-        if prev_bv & (1 << get_basemove_pos(antipode)) != 0 {
-            // then the antipode was already applied
-            (1 << get_basemove_pos(lm)) + (1 << get_basemove_pos(antipode))
-        } else {
-            1 << get_basemove_pos(lm)
-        }
-    } else {
-        0
-    }
-}
-
 /// The underlying struct for representing a configuration of the Rubik's Cube.
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub struct CubeState {
@@ -320,7 +385,7 @@ fn factorial(num: u32) -> u32 {
 // range:
 // corners: [0, 8! - 1]
 // edges: [0, 12! - 1]
-fn get_index_of_permutation(perm: &[u8]) -> u32 {
+pub(crate) fn get_index_of_permutation(perm: &[u8]) -> u32 {
     // 2 bytes suffice for 12!
     let mut fin = 0;
     for i in 0..perm.len() {
@@ -353,6 +418,37 @@ fn get_index_of_orientation(ori: &[i8], num_orientations: u8) -> u16 {
     result
 }
 
+// the inverse of get_index_of_permutation
+pub(crate) fn get_permutation_of_index<const N: usize>(mut index: u32) -> [u8; N] {
+    let mut perm = [0; N];
+    let mut unused: Vec<u8> = (0..N as u8).collect();
+    for (i, slot) in perm.iter_mut().enumerate() {
+        let place = factorial((N - i - 1) as u32);
+        *slot = unused.remove((index / place) as usize);
+        index %= place;
+    }
+    perm
+}
+
+// the inverse of get_index_of_orientation, with twists of 2 written as -1 the
+// way moves leave them
+fn get_orientation_of_index<const N: usize>(mut index: u16, num_orientations: u8) -> [i8; N] {
+    let mut ori = [0; N];
+    let mut sum = 0;
+    for i in (0..N - 1).rev() {
+        ori[i] = (index % num_orientations as u16) as i8;
+        index /= num_orientations as u16;
+        sum += ori[i];
+    }
+    ori[N - 1] = (num_orientations as i8 - sum % num_orientations as i8) % num_orientations as i8;
+    for o in ori.iter_mut() {
+        if *o == 2 {
+            *o = -1;
+        }
+    }
+    ori
+}
+
 pub fn induces_oriented_partition(
     perm: &[u8],
     ori: &[i8],
@@ -405,6 +501,419 @@ pub fn induces_oriented_partition(
     covered_cycles_count == partition.len()
 }
 
+/// Determines whether a permutation could be part of a state inducing the
+/// given partition, ignoring orientation.
+///
+/// This holds exactly when the nontrivial cycles of `perm` have the lengths
+/// of the nontrivial cycles in `partition`, and there are enough fixed
+/// pieces left over to be twisted in place for the partition's 1-cycles.
+pub fn permutation_admits_partition(
+    perm: &[u8],
+    partition: &[(u8, bool)],
+    multi_bv: &mut [u8],
+) -> bool {
+    // reuse memory
+    multi_bv.fill(0);
+    // counts of cycles by length, with fixed pieces counted at index 1
+    let mut actual_counts = [0_u8; EDGES + 1];
+    let mut expected_counts = [0_u8; EDGES + 1];
+    for i in 0..perm.len() {
+        if multi_bv[i] != 0 {
+            continue;
+        }
+        multi_bv[i] = 1;
+        let mut cycle_length = 1;
+        let mut piece = perm[i] as usize;
+        while piece != i {
+            cycle_length += 1;
+            multi_bv[piece] = 1;
+            piece = perm[piece] as usize;
+        }
+        actual_counts[cycle_length] += 1;
+    }
+    for &(cycle_length, _) in partition {
+        match expected_counts.get_mut(cycle_length as usize) {
+            Some(count) => *count += 1,
+            None => return false,
+        }
+    }
+    actual_counts[1] >= expected_counts[1] && actual_counts[2..] == expected_counts[2..]
+}
+
+/// Returns the indices of every permutation of `n` pieces accepted by
+/// `permutation_admits_partition`, without scanning all `n!` permutations.
+///
+/// The permutations are built cycle by cycle, each cycle starting from its
+/// smallest piece and cycles of equal length in increasing order of their
+/// smallest pieces, so that each is produced exactly once.
+pub fn permutation_indices_admitting(n: usize, partition: &[(u8, bool)]) -> Vec<u32> {
+    let mut lengths: Vec<usize> = partition
+        .iter()
+        .map(|&(length, _)| length as usize)
+        .filter(|&length| length > 1)
+        .collect();
+    lengths.sort_unstable();
+    let fixed = partition.len() - lengths.len();
+    let mut indices = vec![];
+    if lengths.iter().sum::<usize>() + fixed <= n {
+        let mut perm: Vec<u8> = (0..n as u8).collect();
+        let mut used = vec![false; n];
+        build_cycles(&lengths, 0, &mut perm, &mut used, &mut indices);
+    }
+    indices
+}
+
+fn build_cycles(
+    lengths: &[usize],
+    min_start: usize,
+    perm: &mut [u8],
+    used: &mut [bool],
+    indices: &mut Vec<u32>,
+) {
+    let Some((&length, rest)) = lengths.split_first() else {
+        indices.push(get_index_of_permutation(perm));
+        return;
+    };
+    for start in min_start..perm.len() {
+        if used[start] {
+            continue;
+        }
+        used[start] = true;
+        let mut cycle = vec![start];
+        extend_cycle(length, rest, &mut cycle, perm, used, indices);
+        used[start] = false;
+    }
+}
+
+// chooses the remaining pieces of a cycle, all larger than its first piece
+fn extend_cycle(
+    length: usize,
+    rest: &[usize],
+    cycle: &mut Vec<usize>,
+    perm: &mut [u8],
+    used: &mut [bool],
+    indices: &mut Vec<u32>,
+) {
+    if cycle.len() == length {
+        for (i, &piece) in cycle.iter().enumerate() {
+            perm[piece] = cycle[(i + 1) % length] as u8;
+        }
+        // equal cycles are built in increasing order of their first piece
+        let min_start = if rest.first() == Some(&length) {
+            cycle[0] + 1
+        } else {
+            0
+        };
+        build_cycles(rest, min_start, perm, used, indices);
+        for &piece in cycle.iter() {
+            perm[piece] = piece as u8;
+        }
+        return;
+    }
+    for piece in cycle[0] + 1..perm.len() {
+        if used[piece] {
+            continue;
+        }
+        used[piece] = true;
+        cycle.push(piece);
+        extend_cycle(length, rest, cycle, perm, used, indices);
+        cycle.pop();
+        used[piece] = false;
+    }
+}
+
+/// Determines whether an orientation vector could be part of a state
+/// inducing the given partition, for some permutation of the pieces.
+///
+/// Pieces can be moved freely, so only how many pieces have each orientation
+/// matters: the pieces must split into one group per cycle whose twists sum
+/// to a nonzero amount exactly for the oriented cycles, with every piece left
+/// over untwisted.
+pub fn orientation_admits_partition(
+    ori: &[i8],
+    partition: &[(u8, bool)],
+    orientation_count: i8,
+) -> bool {
+    let mut totals = [0_u8; 3];
+    for &o in ori {
+        totals[(o + orientation_count) as usize % orientation_count as usize] += 1;
+    }
+    // the sets of (pieces used with each orientation) reachable after assigning
+    // pieces to some prefix of the partition
+    let mut reachable = HashSet::from([[0_u8; 3]]);
+    for &(cycle_length, orients) in partition {
+        let mut next = HashSet::new();
+        for used in reachable {
+            for once in 0..=cycle_length {
+                for twice in 0..=(cycle_length - once) {
+                    if orientation_count == 2 && twice != 0 {
+                        continue;
+                    }
+                    let taken = [cycle_length - once - twice, once, twice];
+                    if (0..3).any(|k| used[k] + taken[k] > totals[k]) {
+                        continue;
+                    }
+                    let twist = (once as i8 + 2 * twice as i8) % orientation_count;
+                    if (twist != 0) == orients {
+                        next.insert([used[0] + taken[0], used[1] + once, used[2] + twice]);
+                    }
+                }
+            }
+        }
+        reachable = next;
+    }
+    reachable
+        .iter()
+        .any(|used| used[1] == totals[1] && used[2] == totals[2])
+}
+
+/// Determines whether the pieces of `tracked` could be placed as they are in
+/// `perm` and `ori` in some state inducing the given partition, no matter
+/// where the other pieces are. Without `ori` the orientations of the tracked
+/// pieces are unknown too.
+///
+/// Following the tracked pieces splits them into cycles that are entirely
+/// tracked, whose length and orientation are known, and open paths, which
+/// can be closed off into cycles of any orientation using untracked pieces.
+/// The known cycles must appear in the partition, and the paths must fit
+/// into its remaining cycles. A cycle of unknown orientation may be either
+/// one of its length, and a fixed piece of unknown orientation may make up
+/// any 1-cycle.
+pub fn partial_admits_partition(
+    perm: &[u8],
+    ori: Option<&[i8]>,
+    tracked: &[u8],
+    partition: &[(u8, bool)],
+    orientation_count: i8,
+) -> bool {
+    let tracked_mask = tracked
+        .iter()
+        .fold(0_u32, |mask, &piece| mask | (1 << piece));
+    let is_tracked = |piece: u8| tracked_mask & (1 << piece) != 0;
+    let mut visited = [false; EDGES];
+    let mut visited_count = 0;
+
+    // a path starts at a position holding a tracked piece whose own home
+    // position holds an untracked one, and ends at the first untracked piece
+    let mut path_sizes = vec![];
+    for head in 0..perm.len() {
+        if !is_tracked(perm[head]) || is_tracked(head as u8) {
+            continue;
+        }
+        let mut size = 1;
+        let mut node = head;
+        while is_tracked(perm[node]) {
+            visited[node] = true;
+            node = perm[node] as usize;
+            size += 1;
+        }
+        visited[node] = true;
+        visited_count += size as usize;
+        path_sizes.push(size);
+    }
+
+    // every other tracked piece lies on a cycle of tracked pieces
+    let mut remaining = partition.to_vec();
+    let mut loose_fixed = 0;
+    for start in 0..perm.len() {
+        if visited[start] || !is_tracked(perm[start]) {
+            continue;
+        }
+        let mut cycle_length = 0;
+        let mut orientation_sum = 0;
+        let mut node = start;
+        loop {
+            visited[node] = true;
+            cycle_length += 1;
+            orientation_sum += ori.map_or(0, |ori| ori[node]);
+            node = perm[node] as usize;
+            if node == start {
+                break;
+            }
+        }
+        let orients = ori.map(|_| orientation_sum % orientation_count != 0);
+        visited_count += cycle_length as usize;
+        if cycle_length == 1 && orients != Some(true) {
+            loose_fixed += orients.is_none() as usize;
+            continue;
+        }
+        let Some(matching) = remaining.iter().position(|&(length, oriented)| {
+            length == cycle_length && orients.is_none_or(|orients| orients == oriented)
+        }) else {
+            return false;
+        };
+        remaining.swap_remove(matching);
+    }
+
+    // fixed pieces of unknown orientation can only make up 1-cycles
+    for _ in 0..loose_fixed {
+        match remaining.iter().position(|&(length, _)| length == 1) {
+            Some(fixed) => remaining.swap_remove(fixed),
+            None => break,
+        };
+    }
+
+    // the paths plus some free pieces must exactly make up the remaining cycles
+    let free_count = perm.len() - visited_count;
+    let path_total: usize = path_sizes.iter().map(|&size| size as usize).sum();
+    let cycle_total: usize = remaining.iter().map(|&(length, _)| length as usize).sum();
+    if path_total > cycle_total || cycle_total - path_total > free_count {
+        return false;
+    }
+    path_sizes.sort_unstable_by(|a, b| b.cmp(a));
+    let mut capacities: Vec<u8> = remaining.iter().map(|&(length, _)| length).collect();
+    fits_in_cycles(&path_sizes, &mut capacities)
+}
+
+// whether the paths of the given sizes can be assigned to cycles without
+// overfilling any of them
+fn fits_in_cycles(path_sizes: &[u8], capacities: &mut [u8]) -> bool {
+    let Some((&size, rest)) = path_sizes.split_first() else {
+        return true;
+    };
+    for i in 0..capacities.len() {
+        // trying one cycle of each free capacity suffices
+        if capacities[i] < size || capacities[..i].contains(&capacities[i]) {
+            continue;
+        }
+        capacities[i] -= size;
+        let fits = fits_in_cycles(rest, capacities);
+        capacities[i] += size;
+        if fits {
+            return true;
+        }
+    }
+    false
+}
+
+// a corner twist in -1..=1, as moves leave them
+fn normalize_twist(twist: i8) -> i8 {
+    match twist.rem_euclid(3) {
+        2 => -1,
+        t => t,
+    }
+}
+
+// the number of ways to place k distinct pieces into n positions
+fn arrangements(n: usize, k: usize) -> usize {
+    (n + 1 - k..=n).product()
+}
+
+/// The number of indices given by `partial_index` for `k` of `n` pieces,
+/// `n!/(n-k)!` times `orientation_count^k` if their orientations count.
+pub fn partial_size(n: usize, k: usize, orientation_count: Option<i8>) -> usize {
+    arrangements(n, k) * orientations(k, orientation_count)
+}
+
+// the number of ways to orient k pieces, 1 if their orientations don't count
+fn orientations(k: usize, orientation_count: Option<i8>) -> usize {
+    orientation_count.map_or(1, |count| (count as usize).pow(k as u32))
+}
+
+/// Computes the index of the positions of the pieces of `tracked` in
+/// `perm`, and of their orientations in `ori` if `orientation_count` is
+/// given, ignoring every other piece. The range is given by `partial_size`.
+pub fn partial_index(
+    perm: &[u8],
+    ori: &[i8],
+    tracked: &[u8],
+    orientation_count: Option<i8>,
+) -> usize {
+    let mut placements = [(0, 0); EDGES];
+    for (placement, &piece) in placements.iter_mut().zip(tracked) {
+        let pos = perm.iter().position(|&p| p == piece).unwrap();
+        let twist = orientation_count.map_or(0, |count| ori[pos].rem_euclid(count));
+        *placement = (pos as u8, twist as u8);
+    }
+    placements_index(perm.len(), &placements[..tracked.len()], orientation_count)
+}
+
+/// Computes the index given by `partial_index` from where each tracked piece
+/// is, in order: its position among the `n` and its twist in
+/// `[0, orientation_count - 1]`, which is ignored without an
+/// `orientation_count`.
+pub fn placements_index(n: usize, placements: &[(u8, u8)], orientation_count: Option<i8>) -> usize {
+    let k = placements.len();
+    let mut used = [false; EDGES];
+    let mut position_index = 0;
+    let mut orientation_index = 0;
+    for (i, &(pos, twist)) in placements.iter().enumerate() {
+        let pos = pos as usize;
+        let smaller_free = used[..pos].iter().filter(|&&u| !u).count();
+        used[pos] = true;
+        position_index += smaller_free * arrangements(n - 1 - i, k - 1 - i);
+        if let Some(count) = orientation_count {
+            orientation_index = orientation_index * count as usize + twist as usize;
+        }
+    }
+    position_index * orientations(k, orientation_count) + orientation_index
+}
+
+/// The inverse of `placements_index` for `k` tracked pieces, whose
+/// placements are the first `k` returned.
+pub fn placements_of_index(
+    n: usize,
+    k: usize,
+    orientation_count: Option<i8>,
+    index: usize,
+) -> [(u8, u8); EDGES] {
+    let mut placements = [(0, 0); EDGES];
+    let mut used = [false; EDGES];
+    let orientation_states = orientations(k, orientation_count);
+    let mut position_index = index / orientation_states;
+    let mut orientation_index = index % orientation_states;
+    for (i, placement) in placements.iter_mut().take(k).enumerate() {
+        let stride = arrangements(n - 1 - i, k - 1 - i);
+        let nth_free = position_index / stride;
+        position_index %= stride;
+        let pos = (0..n).filter(|&p| !used[p]).nth(nth_free).unwrap();
+        used[pos] = true;
+        let rest = orientations(k - 1 - i, orientation_count);
+        let twist = orientation_index / rest;
+        orientation_index %= rest;
+        *placement = (pos as u8, twist as u8);
+    }
+    placements
+}
+
+// the inverse of `partial_index`, placing the untracked pieces in the
+// remaining positions in order, untwisted
+fn from_partial_index<const N: usize>(
+    tracked: &[u8],
+    orientation_count: Option<i8>,
+    index: usize,
+) -> ([u8; N], [i8; N]) {
+    let mut perm = [u8::MAX; N];
+    let mut ori = [0_i8; N];
+    let placements = placements_of_index(N, tracked.len(), orientation_count, index);
+    for (&piece, &(pos, twist)) in tracked.iter().zip(placements.iter()) {
+        perm[pos as usize] = piece;
+        if let Some(count) = orientation_count {
+            let twist = twist as i8;
+            // twists as moves leave them, -1 rather than 2 for corners
+            ori[pos as usize] = if twist * 2 > count {
+                twist - count
+            } else {
+                twist
+            };
+        }
+    }
+    let mut untracked = (0..N as u8).filter(|p| !tracked.contains(p));
+    for slot in perm.iter_mut().filter(|p| **p == u8::MAX) {
+        *slot = untracked.next().unwrap();
+    }
+    (perm, ori)
+}
+
+// the number of orientations of the corners and edges of a pattern, for
+// the orbits whose orientation it tracks
+fn pattern_orientations(pattern: &Pattern) -> (Option<i8>, Option<i8>) {
+    (
+        pattern.corner_orientation().then_some(3),
+        pattern.edge_orientation().then_some(2),
+    )
+}
+
 impl CubeState {
     pub fn from_corners(cp: [u8; CORNERS], co: [i8; CORNERS]) -> Self {
         CubeState {
@@ -414,6 +923,14 @@ impl CubeState {
         }
     }
 
+    pub fn from_edges(ep: [u8; EDGES], eo: [i8; EDGES]) -> Self {
+        CubeState {
+            ep,
+            eo,
+            ..Default::default()
+        }
+    }
+
     fn apply_basemove(&self, m: &BaseMoveToken) -> Self {
         let mov = get_move_matrix(m);
         let oriented_corners = apply_orientation!(&self.co, &mov.co_change, 3);
@@ -444,11 +961,71 @@ impl CubeState {
     }
 
     pub fn corner_state_index(&self) -> u32 {
-        let cp_index = get_index_of_permutation(&self.cp);
-        let co_index = get_index_of_orientation(&self.co, 3);
+        let cp_index = self.corner_permutation_index() as u32;
+        let co_index = self.corner_orientation_index();
         cp_index * u32::pow(3, 7) + (co_index as u32)
     }
 
+    /// The index of the corner permutation alone, in `[0, 8! - 1]`.
+    pub fn corner_permutation_index(&self) -> u16 {
+        get_index_of_permutation(&self.cp) as u16
+    }
+
+    /// The index of the corner orientation alone, in `[0, 3^7 - 1]`.
+    pub fn corner_orientation_index(&self) -> u16 {
+        get_index_of_orientation(&self.co, 3)
+    }
+
+    /// The corners of this state followed by the corners of `other`, as if
+    /// the moves reaching `other` from the solved state were applied to this
+    /// state. The edges are solved.
+    pub fn then_corners(&self, other: &CubeState) -> Self {
+        let mut cp = [0; CORNERS];
+        let mut co = [0; CORNERS];
+        for position in 0..CORNERS {
+            let from = other.cp[position] as usize;
+            cp[position] = self.cp[from];
+            co[position] = normalize_twist(self.co[from] + other.co[position]);
+        }
+        CubeState::from_corners(cp, co)
+    }
+
+    /// The corners of the state undoing this one, with the edges solved.
+    pub fn inverse_corners(&self) -> Self {
+        let mut cp = [0; CORNERS];
+        let mut co = [0; CORNERS];
+        for position in 0..CORNERS {
+            let piece = self.cp[position] as usize;
+            cp[piece] = position as u8;
+            co[piece] = normalize_twist(-self.co[position]);
+        }
+        CubeState::from_corners(cp, co)
+    }
+
+    /// The corners of this state reflected through the plane between the L
+    /// and R faces, with the edges solved. Reflection reverses the direction
+    /// of every twist.
+    pub fn mirror_corners(&self) -> Self {
+        // UBL <-> UBR, UFR <-> UFL, DFL <-> DFR, DBR <-> DBL
+        let mirror = |corner: u8| corner ^ 1;
+        let mut cp = [0; CORNERS];
+        let mut co = [0; CORNERS];
+        for position in 0..CORNERS {
+            cp[mirror(position as u8) as usize] = mirror(self.cp[position]);
+            co[mirror(position as u8) as usize] = normalize_twist(-self.co[position]);
+        }
+        CubeState::from_corners(cp, co)
+    }
+
+    /// The inverse of `corner_state_index`, with the edges solved.
+    pub fn from_corner_state_index(index: u32) -> Self {
+        let co_states = u32::pow(3, 7);
+        CubeState::from_corners(
+            get_permutation_of_index(index / co_states),
+            get_orientation_of_index((index % co_states) as u16, 3),
+        )
+    }
+
     pub fn induces_corner_cycle_type(
         &self,
         cycle_type: &CycleType<u8>,
@@ -463,6 +1040,106 @@ impl CubeState {
         )
     }
 
+    pub fn edge_orientation_index(&self) -> u16 {
+        get_index_of_orientation(&self.eo, 2)
+    }
+
+    pub fn edge_permutation_index(&self) -> u32 {
+        get_index_of_permutation(&self.ep)
+    }
+
+    /// The inverse of `edge_orientation_index`, with the corners solved and
+    /// the edges in place.
+    pub fn from_edge_orientation_index(index: u16) -> Self {
+        CubeState::from_edges(
+            std::array::from_fn(|i| i as u8),
+            get_orientation_of_index(index, 2),
+        )
+    }
+
+    /// The inverse of `edge_permutation_index`, with the corners solved and
+    /// the edges unflipped.
+    pub fn from_edge_permutation_index(index: u32) -> Self {
+        CubeState::from_edges(get_permutation_of_index(index), [0; EDGES])
+    }
+
+    /// Whether the edge orientation of this state could belong to a state
+    /// inducing the edge partition of `cycle_type`.
+    pub fn admits_edge_orientation(&self, cycle_type: &CycleType<u8>) -> bool {
+        orientation_admits_partition(&self.eo, &cycle_type.edge_partition, 2)
+    }
+
+    /// Whether the edge permutation of this state could belong to a state
+    /// inducing the edge partition of `cycle_type`.
+    pub fn admits_edge_permutation(&self, cycle_type: &CycleType<u8>, multi_bv: &mut [u8]) -> bool {
+        permutation_admits_partition(&self.ep, &cycle_type.edge_partition, multi_bv)
+    }
+
+    /// Computes the index of the positions and orientations of the given
+    /// edges, ignoring every other edge. For `k` edges the range is
+    /// `[0, 12!/(12-k)! * 2^k - 1]`.
+    pub fn partial_edge_index(&self, edges: &[u8]) -> usize {
+        partial_index(&self.ep, &self.eo, edges, Some(2))
+    }
+
+    /// The inverse of `partial_edge_index`. The edges not listed are placed
+    /// in the remaining positions in order, unflipped.
+    pub fn from_partial_edges(edges: &[u8], index: usize) -> Self {
+        let (ep, eo) = from_partial_index(edges, Some(2), index);
+        CubeState::from_edges(ep, eo)
+    }
+
+    /// Computes the index of this state in the pattern database over the
+    /// pieces of `pattern`, in `[0, pattern.size() - 1]`.
+    pub fn pattern_index(&self, pattern: &Pattern) -> usize {
+        let (corner_orientations, edge_orientations) = pattern_orientations(pattern);
+        let edges = partial_index(&self.ep, &self.eo, pattern.edges(), edge_orientations);
+        let corners = partial_index(&self.cp, &self.co, pattern.corners(), corner_orientations);
+        corners * partial_size(EDGES, pattern.edges().len(), edge_orientations) + edges
+    }
+
+    /// The inverse of `pattern_index`. The pieces not in the pattern are
+    /// placed in the remaining positions in order, and no piece is twisted
+    /// or flipped unless the pattern tracks its orientation.
+    pub fn from_pattern_index(pattern: &Pattern, index: usize) -> Self {
+        let (corner_orientations, edge_orientations) = pattern_orientations(pattern);
+        let edge_size = partial_size(EDGES, pattern.edges().len(), edge_orientations);
+        let (cp, co) =
+            from_partial_index(pattern.corners(), corner_orientations, index / edge_size);
+        let (ep, eo) = from_partial_index(pattern.edges(), edge_orientations, index % edge_size);
+        CubeState { cp, co, ep, eo }
+    }
+
+    /// Whether the pieces of `pattern` are placed, and oriented if the
+    /// pattern tracks it, as they could be in a state inducing `cycle_type`.
+    pub fn admits_pattern(&self, pattern: &Pattern, cycle_type: &CycleType<u8>) -> bool {
+        partial_admits_partition(
+            &self.cp,
+            pattern.corner_orientation().then_some(&self.co[..]),
+            pattern.corners(),
+            &cycle_type.corner_partition,
+            3,
+        ) && partial_admits_partition(
+            &self.ep,
+            pattern.edge_orientation().then_some(&self.eo[..]),
+            pattern.edges(),
+            &cycle_type.edge_partition,
+            2,
+        )
+    }
+
+    /// Whether the given edges are placed and oriented as they could be in
+    /// a state inducing the edge partition of `cycle_type`.
+    pub fn admits_partial_edges(&self, edges: &[u8], cycle_type: &CycleType<u8>) -> bool {
+        partial_admits_partition(
+            &self.ep,
+            Some(&self.eo),
+            edges,
+            &cycle_type.edge_partition,
+            2,
+        )
+    }
+
     pub fn induces_cycle_type(&self, cycle_type: &CycleType<u8>, multi_bv: &mut [u8]) -> bool {
         self.induces_corner_cycle_type(cycle_type, multi_bv)
             && induces_oriented_partition(
@@ -597,6 +1274,191 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_edge_projections_admit_partition() {
+        let mut multi_bv = vec![0_u8; EDGES.max(CORNERS)];
+        let partition = [(2, true), (2, true)];
+        let swapped = [1, 0, 3, 2, 4, 5, 6, 7, 8, 9, 10, 11];
+        let identity: [u8; EDGES] = std::array::from_fn(|i| i as u8);
+        let flipped = [1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let unflipped = [0; EDGES];
+
+        assert!(induces_oriented_partition(
+            &swapped,
+            &flipped,
+            &partition,
+            2,
+            &mut multi_bv
+        ));
+        assert!(permutation_admits_partition(
+            &swapped,
+            &partition,
+            &mut multi_bv
+        ));
+        assert!(orientation_admits_partition(&flipped, &partition, 2));
+
+        assert!(!permutation_admits_partition(
+            &identity,
+            &partition,
+            &mut multi_bv
+        ));
+        assert!(!orientation_admits_partition(&unflipped, &partition, 2));
+        assert!(!orientation_admits_partition(
+            &[1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0],
+            &partition,
+            2
+        ));
+        assert!(orientation_admits_partition(
+            &[1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0],
+            &[(4, false)],
+            2
+        ));
+        assert!(permutation_admits_partition(
+            &identity,
+            &[(1, true), (1, true)],
+            &mut multi_bv
+        ));
+        assert!(orientation_admits_partition(
+            &[0, 1, 2, 0, 0, 0, 0, 0],
+            &[(1, true), (1, true)],
+            3
+        ));
+        assert!(!orientation_admits_partition(
+            &[0, 1, 2, 0, 0, 0, 0, 0],
+            &[(2, true)],
+            3
+        ));
+    }
+
+    #[test]
+    fn test_state_index_round_trip() {
+        for index in (0..88179840).step_by(99991) {
+            let state = CubeState::from_corner_state_index(index);
+            assert_eq!(state.corner_state_index(), index);
+        }
+        let state = CubeState::default().apply_move_instances(&MoveSequence(
+            parser::parse_scramble("R U F' L2 D").unwrap(),
+        ));
+        let corners = CubeState::from_corner_state_index(state.corner_state_index());
+        assert_eq!(corners.cp, state.cp);
+        assert_eq!(corners.co, state.co);
+        let eo = CubeState::from_edge_orientation_index(state.edge_orientation_index());
+        assert_eq!(eo.edge_orientation_index(), state.edge_orientation_index());
+        let ep = CubeState::from_edge_permutation_index(state.edge_permutation_index());
+        assert_eq!(ep.ep, state.ep);
+    }
+
+    #[test]
+    fn test_permutation_indices_admitting() {
+        let mut multi_bv = [0; EDGES];
+        for partition in [
+            vec![(2, false), (3, true)],
+            vec![(1, true), (2, true), (2, false)],
+            vec![(4, true), (4, false)],
+            vec![(1, true); 9],
+        ] {
+            let mut expected = vec![];
+            for (index, perm) in (0..CORNERS as u8).permutations(CORNERS).enumerate() {
+                if permutation_admits_partition(&perm, &partition, &mut multi_bv) {
+                    expected.push(index as u32);
+                }
+            }
+            let mut actual = permutation_indices_admitting(CORNERS, &partition);
+            actual.sort_unstable();
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_partial_edge_index() {
+        let edges = [1, 4, 7];
+        let size = 12 * 11 * 10 * 8;
+        for index in (0..size).step_by(7) {
+            let state = CubeState::from_partial_edges(&edges, index);
+            assert_eq!(state.partial_edge_index(&edges), index);
+        }
+        assert_eq!(CubeState::default().partial_edge_index(&[0, 1, 2]), 0);
+        let state = CubeState::default().apply_move_instance(&cube_move!(F, Normal));
+        assert_eq!(
+            CubeState::from_partial_edges(&[2, 6], state.partial_edge_index(&[2, 6]))
+                .partial_edge_index(&[2, 6]),
+            state.partial_edge_index(&[2, 6])
+        );
+    }
+
+    #[test]
+    fn test_partial_admits_partition() {
+        let partition = [(2, true), (2, true)];
+        let swapped = [1, 0, 3, 2, 4, 5, 6, 7, 8, 9, 10, 11];
+        let flipped = [1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        for tracked in [
+            &[0][..],
+            &[0, 1],
+            &[1, 2],
+            &[0, 5],
+            &[0, 1, 2, 3],
+            &[4, 5, 6],
+        ] {
+            assert!(partial_admits_partition(
+                &swapped,
+                Some(&flipped),
+                tracked,
+                &partition,
+                2
+            ));
+        }
+        // the first two edges are swapped without a flip
+        assert!(!partial_admits_partition(
+            &swapped,
+            Some(&[0, 0, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0]),
+            &[0, 1],
+            &partition,
+            2
+        ));
+        // which is fine when orientations aren't tracked
+        assert!(partial_admits_partition(
+            &swapped,
+            None,
+            &[0, 1],
+            &partition,
+            2
+        ));
+        // a path of three edges doesn't fit into a 2-cycle
+        let three_cycle = [1, 2, 0, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+        assert!(!partial_admits_partition(
+            &three_cycle,
+            Some(&[0; EDGES]),
+            &[1, 2],
+            &partition,
+            2
+        ));
+        assert!(partial_admits_partition(
+            &three_cycle,
+            Some(&[0; EDGES]),
+            &[1],
+            &partition,
+            2
+        ));
+
+        // tracking every piece is the same as checking the full partition
+        let mut multi_bv = vec![0_u8; EDGES.max(CORNERS)];
+        let all: Vec<u8> = (0..EDGES as u8).collect();
+        let mut state = CubeState::default();
+        for m in parser::parse_scramble("F R U' B2 L D F' U2 R'").unwrap() {
+            state = state.apply_move_instance(&m);
+            for partition in [&partition[..], &[(4, false)], &[(1, true), (1, true)]] {
+                assert_eq!(
+                    partial_admits_partition(&state.ep, Some(&state.eo), &all, partition, 2),
+                    induces_oriented_partition(&state.ep, &state.eo, partition, 2, &mut multi_bv)
+                );
+                assert_eq!(
+                    partial_admits_partition(&state.ep, None, &all, partition, 2),
+                    permutation_admits_partition(&state.ep, partition, &mut multi_bv)
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_induces_corner_cycle_type_all_orients() {
         // we can guarantee the partition length will never be greater than the number of pieces in the orbit
@@ -0,0 +1,494 @@
+//! Lower bounds on the number of moves needed to reach a goal, as used by
+//! `solver::IDASolver` to prune its search.
+//!
+//! Every pruning table implements `Heuristic`, and heuristics can be
+//! combined with `Max` and `Sum` or written as closures with `from_fn`:
+//!
+//! ```no_run
+//! use rusty_rubik::cube::CubeState;
+//! use rusty_rubik::heuristic::{self, Heuristic};
+//! use rusty_rubik::pruning::PruningTables;
+//! use rusty_rubik::solver::IDASolver;
+//! use rusty_rubik::CycleType;
+//!
+//! let cycle_type: CycleType<u8> = "C:1o,2n,3o".parse().unwrap();
+//! let tables = PruningTables::from(&cycle_type);
+//! // at least one move unless the cube is solved
+//! let unsolved = heuristic::from_fn(|state: &CubeState| (*state != CubeState::default()) as u8);
+//! let heuristic = (&tables).max_with(unsolved);
+//! let solution = IDASolver::new(CubeState::default(), &heuristic, cycle_type).solve();
+//! ```
+//!
+//! A heuristic must never overestimate, or the solutions found are not
+//! optimal.
+//!
+//! Tables that are not on disk yet can be generated on a background thread
+//! with `BackgroundTables`, whose search proceeds with a weaker heuristic
+//! until they are ready. Dropping it cancels the generation.
+
+use crate::cube::CubeState;
+use crate::pattern::PatternTable;
+use crate::progress::CancellationToken;
+use crate::pruning::{HValue, PartialEdgeTable, PruningTables, TableError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread::JoinHandle;
+
+/// A lower bound on the number of moves needed to bring a state to a goal.
+///
+/// The bound of a state is computed through a `Node`, which may keep more
+/// than the bound, so that the nodes of the neighbours of a state can be
+/// computed from its own during search. `PruningTables` keeps the exact
+/// depths behind its `Packing::Mod3` tables this way.
+pub trait Heuristic {
+    /// What is known about a state once evaluated.
+    type Node: Copy;
+
+    /// Evaluates a state without knowing any of its neighbours.
+    fn evaluate(&self, state: &CubeState) -> Self::Node;
+
+    /// Evaluates a state one move away from a state evaluated as `parent`,
+    /// as during search.
+    fn evaluate_child(&self, state: &CubeState, parent: Self::Node) -> Self::Node {
+        let _ = parent;
+        self.evaluate(state)
+    }
+
+    /// The lower bound of an evaluated state.
+    fn bound(&self, node: Self::Node) -> u8;
+
+    /// Computes a lower bound on the number of moves needed to bring the
+    /// given state to a goal.
+    fn lower_bound(&self, state: &CubeState) -> u8 {
+        self.bound(self.evaluate(state))
+    }
+
+    /// The larger of this bound and `other`, which is a lower bound whenever
+    /// both are.
+    fn max_with<H: Heuristic>(self, other: H) -> Max<Self, H>
+    where
+        Self: Sized,
+    {
+        Max(self, other)
+    }
+
+    /// The sum of this bound and `other`. This is only a lower bound if no
+    /// move is counted by both, which for pattern databases means that each
+    /// only counts the moves of its own pieces. A face turn moves several
+    /// pieces, so databases over disjoint pieces are not additive as they
+    /// are.
+    fn sum_with<H: Heuristic>(self, other: H) -> Sum<Self, H>
+    where
+        Self: Sized,
+    {
+        Sum(self, other)
+    }
+}
+
+impl<H: Heuristic + ?Sized> Heuristic for &H {
+    type Node = H::Node;
+
+    fn evaluate(&self, state: &CubeState) -> Self::Node {
+        (**self).evaluate(state)
+    }
+
+    fn evaluate_child(&self, state: &CubeState, parent: Self::Node) -> Self::Node {
+        (**self).evaluate_child(state, parent)
+    }
+
+    fn bound(&self, node: Self::Node) -> u8 {
+        (**self).bound(node)
+    }
+}
+
+impl Heuristic for PruningTables {
+    type Node = HValue;
+
+    fn evaluate(&self, state: &CubeState) -> HValue {
+        self.h_value(state)
+    }
+
+    fn evaluate_child(&self, state: &CubeState, parent: HValue) -> HValue {
+        self.h_value_from_parent(state, parent)
+    }
+
+    fn bound(&self, node: HValue) -> u8 {
+        node.h
+    }
+}
+
+impl Heuristic for PartialEdgeTable {
+    type Node = u8;
+
+    fn evaluate(&self, state: &CubeState) -> u8 {
+        self.compute_h_value(state)
+    }
+
+    fn bound(&self, node: u8) -> u8 {
+        node
+    }
+}
+
+impl Heuristic for PatternTable {
+    type Node = u8;
+
+    fn evaluate(&self, state: &CubeState) -> u8 {
+        self.compute_h_value(state)
+    }
+
+    fn bound(&self, node: u8) -> u8 {
+        node
+    }
+}
+
+/// The heuristic bounding every state by 0, which turns a search into a
+/// plain iterative deepening search. This suits searches too short to be
+/// worth loading tables for.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Zero;
+
+impl Heuristic for Zero {
+    type Node = ();
+
+    fn evaluate(&self, _: &CubeState) {}
+
+    fn bound(&self, _: ()) -> u8 {
+        0
+    }
+}
+
+/// The larger of two heuristics, as returned by `Heuristic::max_with`.
+#[derive(Clone, Copy, Debug)]
+pub struct Max<A, B>(pub A, pub B);
+
+impl<A: Heuristic, B: Heuristic> Heuristic for Max<A, B> {
+    type Node = (A::Node, B::Node);
+
+    fn evaluate(&self, state: &CubeState) -> Self::Node {
+        (self.0.evaluate(state), self.1.evaluate(state))
+    }
+
+    fn evaluate_child(&self, state: &CubeState, (a, b): Self::Node) -> Self::Node {
+        (
+            self.0.evaluate_child(state, a),
+            self.1.evaluate_child(state, b),
+        )
+    }
+
+    fn bound(&self, (a, b): Self::Node) -> u8 {
+        self.0.bound(a).max(self.1.bound(b))
+    }
+}
+
+/// The sum of two heuristics, as returned by `Heuristic::sum_with`.
+#[derive(Clone, Copy, Debug)]
+pub struct Sum<A, B>(pub A, pub B);
+
+impl<A: Heuristic, B: Heuristic> Heuristic for Sum<A, B> {
+    type Node = (A::Node, B::Node);
+
+    fn evaluate(&self, state: &CubeState) -> Self::Node {
+        (self.0.evaluate(state), self.1.evaluate(state))
+    }
+
+    fn evaluate_child(&self, state: &CubeState, (a, b): Self::Node) -> Self::Node {
+        (
+            self.0.evaluate_child(state, a),
+            self.1.evaluate_child(state, b),
+        )
+    }
+
+    fn bound(&self, (a, b): Self::Node) -> u8 {
+        self.0.bound(a).saturating_add(self.1.bound(b))
+    }
+}
+
+/// A heuristic computing the bound of each state with a closure, as
+/// returned by `from_fn`.
+#[derive(Clone, Copy, Debug)]
+pub struct FromFn<F>(F);
+
+/// The heuristic computing the bound of each state with `f`.
+pub fn from_fn<F: Fn(&CubeState) -> u8>(f: F) -> FromFn<F> {
+    FromFn(f)
+}
+
+impl<F: Fn(&CubeState) -> u8> Heuristic for FromFn<F> {
+    type Node = u8;
+
+    fn evaluate(&self, state: &CubeState) -> u8 {
+        (self.0)(state)
+    }
+
+    fn bound(&self, node: u8) -> u8 {
+        node
+    }
+}
+
+/// Pruning tables loaded, and generated if need be, on a background thread,
+/// with a `fallback` heuristic such as `Zero` or depth-bounded tables used
+/// until they are ready.
+///
+/// A search switches to the tables as soon as they are ready, keeping the
+/// fallback for the states evaluated before. Every bound either heuristic
+/// gives is a lower bound, so the solutions found are still optimal; they
+/// are only found faster once the tables are ready. If the tables fail to
+/// load, the fallback is kept for good.
+///
+/// `load` is given a token which is cancelled by `cancel` or when the
+/// `BackgroundTables` is dropped, which then waits for the thread to stop.
+/// Loads should watch it, as a `TableStore` does with a `Monitor` made
+/// `with_cancellation(token)`, so that dropping does not block until the
+/// tables are generated.
+///
+/// ```no_run
+/// use rusty_rubik::cube::CubeState;
+/// use rusty_rubik::heuristic::{BackgroundTables, Zero};
+/// use rusty_rubik::progress::Monitor;
+/// use rusty_rubik::solver::IDASolver;
+/// use rusty_rubik::store::TableStore;
+/// use rusty_rubik::CycleType;
+///
+/// let cycle_type: CycleType<u8> = "C:1o,2n,3o".parse().unwrap();
+/// let background = BackgroundTables::spawn(
+///     {
+///         let cycle_type = cycle_type.clone();
+///         move |token| {
+///             TableStore::from_env()
+///                 .with_monitor(Monitor::new().with_cancellation(token))
+///                 .tables(&cycle_type)
+///         }
+///     },
+///     Zero,
+/// );
+/// let solution = IDASolver::new(CubeState::default(), &background, cycle_type).solve();
+/// match background.wait() {
+///     Ok(_) => println!("tables ready"),
+///     Err(err) => println!("{}", err),
+/// }
+/// ```
+pub struct BackgroundTables<H> {
+    tables: Arc<OnceLock<Result<Arc<PruningTables>, TableError>>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+    token: CancellationToken,
+    fallback: H,
+}
+
+/// The node of a state evaluated by `BackgroundTables`, with whichever
+/// heuristic was in use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BackgroundNode<N> {
+    Fallback(N),
+    Tables(HValue),
+}
+
+impl<H> BackgroundTables<H> {
+    /// Starts `load` on a background thread, using `fallback` until it
+    /// returns. `load` should stop once the token it is given is cancelled.
+    pub fn spawn<F>(load: F, fallback: H) -> Self
+    where
+        F: FnOnce(CancellationToken) -> Result<Arc<PruningTables>, TableError> + Send + 'static,
+    {
+        let tables = Arc::new(OnceLock::new());
+        let token = CancellationToken::new();
+        let thread = std::thread::spawn({
+            let tables = tables.clone();
+            let token = token.clone();
+            move || {
+                let _ = tables.set(load(token));
+            }
+        });
+        BackgroundTables {
+            tables,
+            thread: Mutex::new(Some(thread)),
+            token,
+            fallback,
+        }
+    }
+
+    /// Asks the background thread to stop loading the tables. Those already
+    /// loaded are kept; otherwise `wait` returns the error the load stopped
+    /// with, such as `TableError::Cancelled`.
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    /// The tables, once they have been loaded.
+    pub fn tables(&self) -> Option<&Arc<PruningTables>> {
+        self.tables.get()?.as_ref().ok()
+    }
+
+    /// Whether the tables have been loaded, so that searches use them.
+    pub fn is_ready(&self) -> bool {
+        self.tables().is_some()
+    }
+
+    /// Blocks until the background thread is done, returning the tables or
+    /// the reason they could not be loaded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the background thread panicked.
+    pub fn wait(&self) -> Result<&Arc<PruningTables>, &TableError> {
+        if let Some(thread) = self.thread.lock().unwrap().take() {
+            thread
+                .join()
+                .expect("the tables were being loaded in a thread that panicked");
+        }
+        self.tables
+            .get()
+            .expect("the background thread has set the tables")
+            .as_ref()
+    }
+
+    pub fn fallback(&self) -> &H {
+        &self.fallback
+    }
+}
+
+impl<H> Drop for BackgroundTables<H> {
+    /// Cancels the load and waits for the background thread to stop.
+    fn drop(&mut self) {
+        self.cancel();
+        let thread = self.thread.get_mut().unwrap_or_else(|err| err.into_inner());
+        if let Some(thread) = thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl<H: Heuristic> Heuristic for BackgroundTables<H> {
+    type Node = BackgroundNode<H::Node>;
+
+    fn evaluate(&self, state: &CubeState) -> Self::Node {
+        match self.tables() {
+            Some(tables) => BackgroundNode::Tables(tables.evaluate(state)),
+            None => BackgroundNode::Fallback(self.fallback.evaluate(state)),
+        }
+    }
+
+    fn evaluate_child(&self, state: &CubeState, parent: Self::Node) -> Self::Node {
+        match (parent, self.tables()) {
+            (BackgroundNode::Tables(parent), Some(tables)) => {
+                BackgroundNode::Tables(tables.evaluate_child(state, parent))
+            }
+            // the tables became ready after the parent was evaluated
+            (BackgroundNode::Fallback(_), Some(tables)) => {
+                BackgroundNode::Tables(tables.evaluate(state))
+            }
+            (BackgroundNode::Fallback(parent), None) => {
+                BackgroundNode::Fallback(self.fallback.evaluate_child(state, parent))
+            }
+            (BackgroundNode::Tables(_), None) => unreachable!("tables are never unloaded"),
+        }
+    }
+
+    fn bound(&self, node: Self::Node) -> u8 {
+        match node {
+            BackgroundNode::Fallback(node) => self.fallback.bound(node),
+            BackgroundNode::Tables(node) => node.h,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+    use crate::progress::{CancellationToken, Cancelled, Monitor};
+    use crate::solver::IDASolver;
+    use crate::table::Packing;
+    use crate::{pruning, CycleType};
+
+    fn scrambled(scramble: &str) -> CubeState {
+        parser::parse_scramble(scramble)
+            .unwrap()
+            .iter()
+            .fold(CubeState::default(), |state, m| {
+                state.apply_move_instance(m)
+            })
+    }
+
+    #[test]
+    fn test_combinators() {
+        let state = scrambled("R U");
+        let two = from_fn(|_: &CubeState| 2);
+        let three = from_fn(|_: &CubeState| 3);
+        assert_eq!(Zero.lower_bound(&state), 0);
+        assert_eq!(two.max_with(three).lower_bound(&state), 3);
+        assert_eq!(two.max_with(Zero).lower_bound(&state), 2);
+        assert_eq!(two.sum_with(three).lower_bound(&state), 5);
+        let node = (&two).max_with(Zero).evaluate(&state);
+        assert_eq!(Max(&two, Zero).bound(node), 2);
+    }
+
+    #[test]
+    fn test_solve_with_heuristics() {
+        let state = scrambled("R U F'");
+        let solved = CycleType::default();
+        let solution = IDASolver::new(state.clone(), &Zero, solved.clone()).solve();
+        assert_eq!(solution.len(), 3);
+        assert_eq!(state.apply_move_instances(&solution), CubeState::default());
+
+        // at least one move from any state that isn't solved
+        let unsolved = from_fn(|state: &CubeState| (*state != CubeState::default()) as u8);
+        let solution = IDASolver::new(state, &Zero.max_with(unsolved), solved.clone()).solve();
+        assert_eq!(solution.len(), 3);
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let mut solver = IDASolver::new(scrambled("R U F' L2 D B"), &Zero, solved)
+            .with_monitor(Monitor::new().with_cancellation(token));
+        assert!(matches!(solver.try_solve(), Err(Cancelled)));
+        assert!(solver.nodes() > 0);
+    }
+
+    #[test]
+    fn test_background_tables() {
+        let state = scrambled("R U F' D");
+        let (ready, wait) = std::sync::mpsc::channel::<()>();
+        let background = BackgroundTables::spawn(
+            move |_| {
+                wait.recv().unwrap();
+                Ok(pruning::test_tables(Packing::Nibble))
+            },
+            Zero,
+        );
+        assert!(!background.is_ready());
+        let parent = background.evaluate(&state);
+        assert_eq!(parent, BackgroundNode::Fallback(()));
+        assert_eq!(background.bound(parent), 0);
+
+        ready.send(()).unwrap();
+        let tables = background.wait().unwrap().clone();
+        assert!(background.is_ready());
+        let child = state.apply_move_instance(&crate::cube::ALL_MOVES[0]);
+        assert_eq!(
+            background.evaluate_child(&child, parent),
+            BackgroundNode::Tables(tables.h_value(&child))
+        );
+        assert_eq!(background.lower_bound(&state), tables.lower_bound(&state));
+
+        // a failed load keeps the fallback
+        let missing =
+            BackgroundTables::spawn(|_| Err(TableError::Missing("corners.pt".into())), Zero);
+        assert!(matches!(missing.wait(), Err(TableError::Missing(_))));
+        assert!(!missing.is_ready());
+        let solution = IDASolver::new(scrambled("R U"), &missing, CycleType::default()).solve();
+        assert_eq!(solution.len(), 2);
+
+        // cancelling stops the load, and dropping cancels it
+        let (stopped, stop) = std::sync::mpsc::channel();
+        let load = move |token: CancellationToken| {
+            while !token.is_cancelled() {
+                std::thread::yield_now();
+            }
+            stopped.send(()).unwrap();
+            Err(TableError::Cancelled("corners".into()))
+        };
+        let cancelled = BackgroundTables::spawn(load.clone(), Zero);
+        cancelled.cancel();
+        assert!(matches!(cancelled.wait(), Err(TableError::Cancelled(_))));
+        drop(BackgroundTables::spawn(load, Zero));
+        assert_eq!(stop.try_iter().count(), 2);
+    }
+}
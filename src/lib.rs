@@ -10,14 +10,397 @@
 //!
 //!
 
+use std::fmt::Display;
+use std::str::FromStr;
+
 pub mod cube;
+pub mod heuristic;
+pub mod move_table;
 pub mod parser;
+pub mod pattern;
+pub mod progress;
 pub mod pruning;
-pub mod puzzle;
+pub mod random;
 pub mod solver;
+pub mod store;
+pub mod symmetry;
+pub mod table;
+pub mod table_file;
 
-#[derive(Default)]
+/// The cycle structure a state should induce on each orbit of pieces.
+///
+/// Each entry is a cycle length together with whether the pieces in that
+/// cycle have a nonzero net orientation. Pieces that are solved in place
+/// are not listed.
+///
+/// Cycle types are written with a compact tag syntax, which `Display`
+/// produces and `FromStr` accepts:
+///
+/// ```text
+/// C:1o,2n,3o E:2o,2o
+/// ```
+///
+/// The `C:` section lists the corner cycles and the `E:` section the edge
+/// cycles. Each cycle is its length followed by `o` (oriented) or `n` (not
+/// oriented). A section with no cycles may be omitted, and `Display` always
+/// writes cycles in canonical order (by length, then unoriented before
+/// oriented).
+///
+/// ```
+/// use rusty_rubik::CycleType;
+///
+/// let cycle_type: CycleType<u8> = "C:3o,1o,2n".parse().unwrap();
+/// assert_eq!(cycle_type.corner_partition, vec![(3, true), (1, true), (2, false)]);
+/// assert_eq!(cycle_type.to_string(), "C:1o,2n,3o");
+/// ```
+#[derive(Default, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct CycleType<T> {
     pub corner_partition: Vec<(T, bool)>,
     pub edge_partition: Vec<(T, bool)>,
 }
+
+impl<T: Ord> CycleType<T> {
+    /// Sorts both partitions into canonical order, so that cycle types that
+    /// only differ in the order of their cycles compare equal.
+    pub fn canonicalize(&mut self) {
+        self.corner_partition.sort();
+        self.edge_partition.sort();
+    }
+}
+
+impl<T: Ord + Clone> CycleType<T> {
+    /// Returns a copy of this cycle type in canonical order.
+    pub fn canonical(&self) -> Self {
+        let mut canonical = self.clone();
+        canonical.canonicalize();
+        canonical
+    }
+}
+
+// writes each cycle of a partition as its length followed by `o` or `n`, the
+// form shared by cycle type tags and pruning table file names
+pub(crate) fn write_cycles<T: Display>(
+    w: &mut impl std::fmt::Write,
+    partition: &[(T, bool)],
+    separator: &str,
+) -> std::fmt::Result {
+    for (i, (length, orients)) in partition.iter().enumerate() {
+        if i != 0 {
+            w.write_str(separator)?;
+        }
+        write!(w, "{}{}", length, if *orients { "o" } else { "n" })?;
+    }
+    Ok(())
+}
+
+fn write_partition<T: Display>(
+    f: &mut std::fmt::Formatter<'_>,
+    label: &str,
+    partition: &[(T, bool)],
+) -> std::fmt::Result {
+    write!(f, "{}:", label)?;
+    write_cycles(f, partition, ",")
+}
+
+impl<T: Display + Ord + Clone> Display for CycleType<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let canonical = self.canonical();
+        let has_edges = !canonical.edge_partition.is_empty();
+        if !canonical.corner_partition.is_empty() || !has_edges {
+            write_partition(f, "C", &canonical.corner_partition)?;
+            if has_edges {
+                write!(f, " ")?;
+            }
+        }
+        if has_edges {
+            write_partition(f, "E", &canonical.edge_partition)?;
+        }
+        Ok(())
+    }
+}
+
+/// The error returned when a string is not a valid cycle type tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseCycleTypeError(String);
+
+impl Display for ParseCycleTypeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid cycle type: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseCycleTypeError {}
+
+fn parse_partition<T: FromStr>(list: &str) -> Result<Vec<(T, bool)>, ParseCycleTypeError> {
+    if list.is_empty() {
+        return Ok(vec![]);
+    }
+    list.split(',')
+        .map(|cycle| {
+            let (length, orients) = match cycle.strip_suffix('o') {
+                Some(length) => (length, true),
+                None => (
+                    cycle.strip_suffix('n').ok_or_else(|| {
+                        ParseCycleTypeError(format!("cycle `{}` must end in `o` or `n`", cycle))
+                    })?,
+                    false,
+                ),
+            };
+            let length = length
+                .parse()
+                .map_err(|_| ParseCycleTypeError(format!("bad cycle length in `{}`", cycle)))?;
+            Ok((length, orients))
+        })
+        .collect()
+}
+
+impl<T: FromStr> FromStr for CycleType<T> {
+    type Err = ParseCycleTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut corner_partition = None;
+        let mut edge_partition = None;
+        for section in s.split_whitespace() {
+            let (slot, list) = if let Some(list) = section.strip_prefix("C:") {
+                (&mut corner_partition, list)
+            } else if let Some(list) = section.strip_prefix("E:") {
+                (&mut edge_partition, list)
+            } else {
+                return Err(ParseCycleTypeError(format!(
+                    "section `{}` must start with `C:` or `E:`",
+                    section
+                )));
+            };
+            if slot.is_some() {
+                return Err(ParseCycleTypeError(format!(
+                    "section `{}` is repeated",
+                    section
+                )));
+            }
+            *slot = Some(parse_partition(list)?);
+        }
+        if corner_partition.is_none() && edge_partition.is_none() {
+            return Err(ParseCycleTypeError("no sections given".to_string()));
+        }
+        Ok(CycleType {
+            corner_partition: corner_partition.unwrap_or_default(),
+            edge_partition: edge_partition.unwrap_or_default(),
+        })
+    }
+}
+
+impl CycleType<u8> {
+    /// The order of any state inducing this cycle type: the least common
+    /// multiple of its cycle lengths, where an oriented corner cycle counts
+    /// three times its length and an oriented edge cycle twice.
+    ///
+    /// ```
+    /// use rusty_rubik::CycleType;
+    ///
+    /// let cycle_type: CycleType<u8> = "C:3o,2n E:4o".parse().unwrap();
+    /// assert_eq!(cycle_type.order(), 72);
+    /// ```
+    pub fn order(&self) -> u64 {
+        fn gcd(a: u64, b: u64) -> u64 {
+            if b == 0 {
+                a
+            } else {
+                gcd(b, a % b)
+            }
+        }
+        let corners = self
+            .corner_partition
+            .iter()
+            .map(|&(length, orients)| length as u64 * if orients { 3 } else { 1 });
+        let edges = self
+            .edge_partition
+            .iter()
+            .map(|&(length, orients)| length as u64 * if orients { 2 } else { 1 });
+        corners
+            .chain(edges)
+            .fold(1, |order, cycle| order / gcd(order, cycle) * cycle)
+    }
+}
+
+/// A set of cycle types, any of which a search may reach.
+///
+/// Pruning tables and `solver::IDASolver` take their goal as `Targets`, so
+/// that one table serves a whole family of cycle types. A single
+/// `CycleType` converts into the set holding only itself. The cycle types
+/// are kept canonical, sorted and without duplicates, so that equal sets
+/// compare equal.
+///
+/// Targets are written as their cycle types joined by `|`:
+///
+/// ```
+/// use rusty_rubik::Targets;
+///
+/// let targets: Targets = "C:2n,2n | C:3o".parse().unwrap();
+/// assert_eq!(targets.len(), 2);
+/// assert_eq!(targets.to_string(), "C:2n,2n | C:3o");
+/// ```
+#[derive(Default, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Targets(Vec<CycleType<u8>>);
+
+impl Targets {
+    /// The set of the given cycle types.
+    pub fn new(cycle_types: impl IntoIterator<Item = CycleType<u8>>) -> Self {
+        let mut cycle_types: Vec<CycleType<u8>> = cycle_types
+            .into_iter()
+            .map(|cycle_type| cycle_type.canonical())
+            .collect();
+        cycle_types.sort_by(|a, b| {
+            (&a.corner_partition, &a.edge_partition).cmp(&(&b.corner_partition, &b.edge_partition))
+        });
+        cycle_types.dedup();
+        Targets(cycle_types)
+    }
+
+    /// Every cycle type of the corners alone, with the edges solved, that
+    /// satisfies `predicate`. Only cycle types some state can induce are
+    /// included: the corner permutation is even, every fixed corner is
+    /// twisted, and no cycle is the only oriented one.
+    ///
+    /// ```
+    /// use rusty_rubik::Targets;
+    ///
+    /// let targets = Targets::corners_where(|cycle_type| cycle_type.order() >= 30);
+    /// assert_eq!(targets.to_string(), "C:3o,5o");
+    /// ```
+    pub fn corners_where(predicate: impl Fn(&CycleType<u8>) -> bool) -> Self {
+        fn partitions(
+            remaining: u8,
+            largest: u8,
+            prefix: &mut Vec<(u8, bool)>,
+            out: &mut Vec<Vec<(u8, bool)>>,
+        ) {
+            out.push(prefix.clone());
+            for length in 2..=remaining.min(largest) {
+                for orients in [false, true] {
+                    prefix.push((length, orients));
+                    partitions(remaining - length, length, prefix, out);
+                    prefix.pop();
+                }
+            }
+        }
+        let mut moved = vec![];
+        partitions(
+            cube::CORNERS as u8,
+            cube::CORNERS as u8,
+            &mut vec![],
+            &mut moved,
+        );
+        let cycle_types = moved.into_iter().flat_map(|moved| {
+            let moved_len: u8 = moved.iter().map(|&(length, _)| length).sum();
+            // a fixed corner is only listed once twisted
+            (0..=cube::CORNERS as u8 - moved_len).map(move |twisted| {
+                let mut corner_partition = moved.clone();
+                corner_partition.extend((0..twisted).map(|_| (1, true)));
+                CycleType {
+                    corner_partition,
+                    edge_partition: vec![],
+                }
+            })
+        });
+        Targets::new(cycle_types.filter(|cycle_type| {
+            let partition = &cycle_type.corner_partition;
+            let even = partition
+                .iter()
+                .map(|&(length, _)| length as usize - 1)
+                .sum::<usize>()
+                % 2
+                == 0;
+            let oriented = partition.iter().filter(|&&(_, orients)| orients).count();
+            even && oriented != 1 && predicate(cycle_type)
+        }))
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, CycleType<u8>> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Whether some target moves or twists an edge, so that the edges need
+    /// tables of their own.
+    pub fn has_edges(&self) -> bool {
+        self.iter()
+            .any(|cycle_type| !cycle_type.edge_partition.is_empty())
+    }
+
+    /// The targets with the cycles of the edges dropped.
+    pub fn corners(&self) -> Targets {
+        Targets::new(self.iter().map(|cycle_type| CycleType {
+            corner_partition: cycle_type.corner_partition.clone(),
+            edge_partition: vec![],
+        }))
+    }
+
+    /// The targets with the cycles of the corners dropped.
+    pub fn edges(&self) -> Targets {
+        Targets::new(self.iter().map(|cycle_type| CycleType {
+            corner_partition: vec![],
+            edge_partition: cycle_type.edge_partition.clone(),
+        }))
+    }
+}
+
+impl From<CycleType<u8>> for Targets {
+    fn from(cycle_type: CycleType<u8>) -> Self {
+        Targets::new([cycle_type])
+    }
+}
+
+impl From<&CycleType<u8>> for Targets {
+    fn from(cycle_type: &CycleType<u8>) -> Self {
+        Targets::new([cycle_type.clone()])
+    }
+}
+
+impl From<&Targets> for Targets {
+    fn from(targets: &Targets) -> Self {
+        targets.clone()
+    }
+}
+
+impl FromIterator<CycleType<u8>> for Targets {
+    fn from_iter<I: IntoIterator<Item = CycleType<u8>>>(iter: I) -> Self {
+        Targets::new(iter)
+    }
+}
+
+impl<'a> IntoIterator for &'a Targets {
+    type Item = &'a CycleType<u8>;
+    type IntoIter = std::slice::Iter<'a, CycleType<u8>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// a single target is written exactly as its cycle type
+impl Display for Targets {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, cycle_type) in self.iter().enumerate() {
+            if i != 0 {
+                write!(f, " | ")?;
+            }
+            write!(f, "{}", cycle_type)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Targets {
+    type Err = ParseCycleTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split('|').map(str::parse).collect()
+    }
+}
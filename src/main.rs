@@ -1,23 +1,285 @@
 use rusty_rubik::cube::CubeState;
-use rusty_rubik::pruning::*;
-use rusty_rubik::solver::IDASolver;
-use rusty_rubik::CycleType;
+use rusty_rubik::heuristic::{BackgroundTables, Heuristic};
+use rusty_rubik::progress::{Event, Monitor};
+use rusty_rubik::pruning::{self, PruningTables, TableError, TablePolicy};
+use rusty_rubik::solver::{IDASolver, TableWalkSolver};
+use rusty_rubik::store::TableStore;
+use rusty_rubik::{CycleType, Targets};
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Instant;
 
+const USAGE: &str = "\
+usage: rusty-rubik [--table-dir DIR] [--load-only | --regenerate] [--depth-bound K]
+                   [--background | --ignore-edges] [TARGETS]
+       rusty-rubik [--table-dir DIR] tables [list|prune]
+       rusty-rubik [--table-dir DIR] tables verify [FILE...]
+       rusty-rubik [--table-dir DIR] tables stats [FILE...]
+
+Tables are kept in DIR, or else in $RUSTY_RUBIK_TABLE_DIR, or else in
+rusty-rubik in the user's cache directory. Missing tables are generated
+unless --load-only is given, and --regenerate replaces existing ones.
+--depth-bound stops generating tables at depth K, for a quick search.
+--background generates missing tables while searching with tables bounded
+at depth 4, switching to the full tables once they are ready, and waits for
+them to be written after printing the solution.
+--ignore-edges brings the corners into TARGETS whatever becomes of the edges,
+reading the solution off the corner table instead of searching; TARGETS must
+then have no edge cycles.
+TARGETS is a cycle type such as \"C:1o,2n,3o E:2o,2o\", or several joined
+by `|` to search for whichever is nearest.
+`tables verify` checks the given table files, or every table in DIR, and
+`tables stats` shows how many states they have at each depth.";
+
+// The depth at which the tables searched with while the full ones are
+// generated in the background stop.
+const FALLBACK_DEPTH_BOUND: u8 = 4;
+
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
 fn main() {
-    let cycle_type = CycleType {
-        corner_partition: vec![(1, true), (2, false), (3, true)],
-        // edge_partition: vec![(2, true), (2, true)],
-        ..Default::default()
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", USAGE);
+        return;
+    }
+    let store = match args.iter().position(|arg| arg == "--table-dir") {
+        Some(i) if i + 1 < args.len() => {
+            let dir = args.remove(i + 1);
+            args.remove(i);
+            TableStore::new(dir)
+        }
+        Some(_) => fail(USAGE),
+        None => TableStore::from_env(),
+    };
+    let mut policy = TablePolicy::LoadOrGenerate;
+    let mut background = false;
+    let mut ignore_edges = false;
+    args.retain(|arg| match arg.as_str() {
+        "--load-only" => {
+            policy = TablePolicy::LoadOnly;
+            false
+        }
+        "--regenerate" => {
+            policy = TablePolicy::Regenerate;
+            false
+        }
+        "--background" => {
+            background = true;
+            false
+        }
+        "--ignore-edges" => {
+            ignore_edges = true;
+            false
+        }
+        _ => true,
+    });
+    let mut store = store.with_policy(policy).with_monitor(monitor());
+    if let Some(i) = args.iter().position(|arg| arg == "--depth-bound") {
+        let bound = args
+            .get(i + 1)
+            .and_then(|bound| bound.parse().ok())
+            .unwrap_or_else(|| fail(USAGE));
+        args.drain(i..i + 2);
+        store = store.with_depth_bound(bound);
+    }
+
+    let targets = match args.first().map(String::as_str) {
+        Some("tables") => {
+            return tables(
+                &store,
+                args.get(1).map(String::as_str),
+                &args[2.min(args.len())..],
+            )
+        }
+        // e.g. `rusty-rubik "C:1o,2n,3o E:2o,2o"` or `rusty-rubik "C:1o,7o | C:2o,3o,2o,1o"`
+        Some(arg) => arg.parse().unwrap_or_else(|err| fail(err)),
+        None => CycleType {
+            corner_partition: vec![(1, true), (2, false), (3, true)],
+            ..Default::default()
+        }
+        .into(),
     };
+    match (background, ignore_edges) {
+        (true, true) => fail(USAGE),
+        (true, false) => solve_in_background(store, policy, targets),
+        (false, true) => walk(&store, &targets),
+        (false, false) => {
+            let tables = load(&store, &targets).unwrap_or_else(|err| fail(err));
+            search(tables.as_ref(), targets);
+        }
+    }
+}
+
+// prints the progress of generating tables and searching
+fn monitor() -> Monitor {
+    Monitor::new().with_observer(|event: &Event| println!("{}", event))
+}
+
+fn tables(store: &TableStore, command: Option<&str>, files: &[String]) {
+    match command {
+        None | Some("list") => {
+            let tables = store.list().unwrap_or_else(|err| fail(err));
+            println!("{} tables in {}", tables.len(), store.dir().display());
+            for table in tables.iter() {
+                println!("  {}", table);
+            }
+            let total: u64 = tables.iter().map(|table| table.size).sum();
+            println!("{:.1} MiB in total", total as f64 / (1 << 20) as f64);
+        }
+        Some("prune") => {
+            let removed = store.prune(|_| true).unwrap_or_else(|err| fail(err));
+            for path in removed.iter() {
+                println!("Removed {}", path.display());
+            }
+            println!("{} invalid tables removed", removed.len());
+        }
+        Some("verify") => {
+            let paths = table_paths(store, files);
+            let mut failed = 0;
+            for path in paths.iter() {
+                let now = Instant::now();
+                match pruning::verify_file(path) {
+                    Ok(_) => println!("{} verified in {:.2?}", path.display(), now.elapsed()),
+                    Err(err) => {
+                        println!("{}", err);
+                        failed += 1;
+                    }
+                }
+            }
+            if failed > 0 {
+                fail(format!(
+                    "{} of {} tables failed verification",
+                    failed,
+                    paths.len()
+                ));
+            }
+            println!("{} tables verified", paths.len());
+        }
+        Some("stats") => {
+            let paths = table_paths(store, files);
+            let mut failed = 0;
+            for path in paths.iter() {
+                let (info, stats) = match pruning::read_stats(path) {
+                    Ok(stats) => stats,
+                    Err(err) => {
+                        println!("{}", err);
+                        failed += 1;
+                        continue;
+                    }
+                };
+                println!(
+                    "{}: {} for {}",
+                    path.display(),
+                    info.header.coordinate,
+                    info.header.targets
+                );
+                println!("  {}", stats);
+                for (depth, count) in stats.histogram.iter().enumerate() {
+                    println!(
+                        "  {:>2}: {:>12} ({:.4}%)",
+                        depth,
+                        count,
+                        *count as f64 / stats.states() as f64 * 100.0
+                    );
+                }
+            }
+            if failed > 0 {
+                fail(format!(
+                    "{} of {} tables could not be read",
+                    failed,
+                    paths.len()
+                ));
+            }
+        }
+        Some(_) => fail(USAGE),
+    }
+}
 
-    let mut tag = "corners".to_string();
-    for &(corner, orient) in cycle_type.corner_partition.iter() {
-        tag.push_str(&format!("{}{}", corner, if orient { "o" } else { "n" }));
+// the given table files, or else every table in the store, leaving out
+// files that are still being written
+fn table_paths(store: &TableStore, files: &[String]) -> Vec<PathBuf> {
+    if files.is_empty() {
+        let tables = store.list().unwrap_or_else(|err| fail(err));
+        tables
+            .into_iter()
+            .filter(|table| !table.is_partial())
+            .map(|table| table.path)
+            .collect()
+    } else {
+        files.iter().map(PathBuf::from).collect()
     }
-    let pruning_tables = PruningTables::from(&tag, &cycle_type);
+}
+
+// the tables for some targets, with partial edge pattern databases when
+// some target moves the edges
+fn load(store: &TableStore, targets: &Targets) -> Result<Arc<PruningTables>, TableError> {
+    if !targets.has_edges() {
+        store.tables(targets)
+    } else {
+        store.tables_with_partial_edges(targets, &[&[0, 1, 2, 3, 4, 5], &[6, 7, 8, 9, 10, 11]])
+    }
+}
+
+// searches with the tables of `store` if they are on disk already, or else
+// with depth-bounded ones while those of `store` are loaded in the
+// background, waiting for them to be ready once a solution is found
+fn solve_in_background(store: TableStore, policy: TablePolicy, targets: Targets) {
+    let store = store.with_policy(TablePolicy::LoadOnly);
+    if policy != TablePolicy::Regenerate {
+        if let Ok(tables) = load(&store, &targets) {
+            return search(tables.as_ref(), targets);
+        }
+    }
+    let fallback = TableStore::new(store.dir())
+        .with_policy(policy)
+        .with_depth_bound(FALLBACK_DEPTH_BOUND)
+        .with_monitor(monitor());
+    let fallback = load(&fallback, &targets).unwrap_or_else(|err| fail(err));
+    let store = store.with_policy(policy);
+    let heuristic = BackgroundTables::spawn(
+        {
+            let targets = targets.clone();
+            move |token| {
+                let store = store.with_monitor(monitor().with_cancellation(token));
+                load(&store, &targets)
+            }
+        },
+        fallback.as_ref(),
+    );
+    search(&heuristic, targets);
+    if !heuristic.is_ready() {
+        println!("Waiting for the full tables to be ready");
+    }
+    match heuristic.wait() {
+        Ok(_) => println!("Full tables ready"),
+        Err(err) => eprintln!("Full tables not loaded: {}", err),
+    }
+}
+
+// reads a solution for the corners off the corner table of `targets`
+fn walk(store: &TableStore, targets: &Targets) {
+    if targets.has_edges() {
+        fail("--ignore-edges needs targets without edge cycles");
+    }
+    let tables = store.tables(targets).unwrap_or_else(|err| fail(err));
+    let now = Instant::now();
+    let walk = TableWalkSolver::ignoring_edges(CubeState::default(), &tables)
+        .expect("the targets have no edge cycles");
+    let solution = walk.solve().unwrap_or_else(|| {
+        fail("no solution within the depth bound of the tables, or the targets are impossible")
+    });
+    println!("{}", solution);
+    println!("Found corner solution in {:.2?}", now.elapsed());
+}
+
+fn search<H: Heuristic + ?Sized>(heuristic: &H, targets: Targets) {
     let now = Instant::now();
-    let mut solver = IDASolver::new(CubeState::default(), &pruning_tables, cycle_type);
+    let mut solver =
+        IDASolver::new(CubeState::default(), heuristic, targets).with_monitor(monitor());
     let solution = solver.solve();
     let elapsed = now.elapsed();
     println!("{}", solution);
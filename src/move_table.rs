@@ -0,0 +1,143 @@
+//! Move tables for the coordinates that index pruning tables.
+//!
+//! A coordinate is a number identifying the state of part of the cube, such
+//! as `CubeState::corner_permutation_index`. A `MoveTable` records the
+//! coordinate reached by applying each move in `cube::ALL_MOVES` to each
+//! coordinate, so that table generation can follow moves with lookups rather
+//! than by building and turning whole `CubeState`s.
+
+use crate::cube::{self, CubeState};
+use rayon::prelude::*;
+
+/// The number of moves in `cube::ALL_MOVES`.
+pub const MOVES: usize = cube::ALL_MOVES.len();
+
+/// The coordinates reached by each move from each coordinate.
+pub struct MoveTable {
+    entries: Vec<[u32; MOVES]>,
+}
+
+impl MoveTable {
+    /// Builds the move table of a coordinate with `size` values. `state`
+    /// must return a state with the given coordinate, as computed by
+    /// `coordinate`.
+    pub fn new(
+        size: usize,
+        state: impl Fn(usize) -> CubeState + Sync,
+        coordinate: impl Fn(&CubeState) -> usize + Sync,
+    ) -> Self {
+        let entries = (0..size)
+            .into_par_iter()
+            .map(|from| {
+                let state = state(from);
+                cube::ALL_MOVES.map(|m| coordinate(&state.apply_move_instance(&m)) as u32)
+            })
+            .collect();
+        MoveTable { entries }
+    }
+
+    /// The move table of `CubeState::corner_permutation_index`.
+    pub fn corner_permutation() -> Self {
+        Self::new(
+            CORNER_PERMUTATIONS,
+            |cp| CubeState::from_corner_state_index((cp * CORNER_ORIENTATIONS) as u32),
+            |state| state.corner_permutation_index() as usize,
+        )
+    }
+
+    /// The move table of `CubeState::corner_orientation_index`.
+    pub fn corner_orientation() -> Self {
+        Self::new(
+            CORNER_ORIENTATIONS,
+            |co| CubeState::from_corner_state_index(co as u32),
+            |state| state.corner_orientation_index() as usize,
+        )
+    }
+
+    /// The move table of `CubeState::edge_orientation_index`.
+    pub fn edge_orientation() -> Self {
+        Self::new(
+            1 << (cube::EDGES - 1),
+            |eo| CubeState::from_edge_orientation_index(eo as u16),
+            |state| state.edge_orientation_index() as usize,
+        )
+    }
+
+    /// The move table of `CubeState::partial_edge_index` for a single edge,
+    /// `2 * position + flip`. Moves carry each edge along regardless of the
+    /// others, so it follows any edge, and coordinates over several edges
+    /// can be moved one edge at a time.
+    pub fn edge() -> Self {
+        Self::new(
+            2 * cube::EDGES,
+            |edge| CubeState::from_partial_edges(&[0], edge),
+            |state| state.partial_edge_index(&[0]),
+        )
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The coordinate reached by applying `cube::ALL_MOVES[move_index]` to a
+    /// state with the given coordinate.
+    pub fn get(&self, coordinate: usize, move_index: usize) -> usize {
+        self.entries[coordinate][move_index] as usize
+    }
+
+    /// The coordinates reached by applying each move in `cube::ALL_MOVES`.
+    pub fn neighbours(&self, coordinate: usize) -> [usize; MOVES] {
+        self.entries[coordinate].map(|c| c as usize)
+    }
+}
+
+/// The number of corner permutations, 8!.
+pub const CORNER_PERMUTATIONS: usize = 40320;
+/// The number of corner orientations, 3^7.
+pub const CORNER_ORIENTATIONS: usize = 2187;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_corner_move_tables() {
+        let cp = MoveTable::corner_permutation();
+        let co = MoveTable::corner_orientation();
+        assert_eq!(
+            (cp.len(), co.len()),
+            (CORNER_PERMUTATIONS, CORNER_ORIENTATIONS)
+        );
+        for index in (0..CORNER_PERMUTATIONS * CORNER_ORIENTATIONS).step_by(7919) {
+            let state = CubeState::from_corner_state_index(index as u32);
+            for (move_index, m) in cube::ALL_MOVES.iter().enumerate() {
+                let next = state.apply_move_instance(m).corner_state_index() as usize;
+                let from = (index / CORNER_ORIENTATIONS, index % CORNER_ORIENTATIONS);
+                assert_eq!(
+                    next,
+                    cp.get(from.0, move_index) * CORNER_ORIENTATIONS + co.get(from.1, move_index)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_edge_move_table() {
+        let edge = MoveTable::edge();
+        assert_eq!(edge.len(), 2 * cube::EDGES);
+        let state = CubeState::default().apply_move_instances(&cube::MoveSequence::from(
+            crate::parser::parse_scramble("R U F' L2 D B").unwrap(),
+        ));
+        for piece in 0..cube::EDGES as u8 {
+            let from = state.partial_edge_index(&[piece]);
+            for (move_index, m) in cube::ALL_MOVES.iter().enumerate() {
+                let next = state.apply_move_instance(m).partial_edge_index(&[piece]);
+                assert_eq!(next, edge.get(from, move_index));
+            }
+        }
+    }
+}
@@ -2,54 +2,218 @@
 
 use crate::cube::*;
 use regex::Regex;
+use std::io::BufRead;
 use std::str::FromStr;
+use std::sync::LazyLock;
+
+// A single move at the start of a token, with the face in the first group and
+// the (possibly empty) amount in the second. These are compiled once and
+// shared by every call, since batch parsing goes through them for every line.
+static RE_SINGMASTER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^([UDLRFB])(2'|2|'|)").unwrap());
+static RE_NUMERIC: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^([UDLRFB])([123])").unwrap());
+static RE_TWSEARCH: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^([UDLRFB])(2'|[123]|'|)").unwrap());
+
+fn move_regex(notation: Notation) -> &'static Regex {
+    match notation {
+        Notation::Singmaster | Notation::Sign | Notation::Compact => &RE_SINGMASTER,
+        Notation::Numeric => &RE_NUMERIC,
+        Notation::Twsearch => &RE_TWSEARCH,
+    }
+}
 
 fn parse_base_move(token: &str) -> Result<BaseMoveToken, strum::ParseError> {
     BaseMoveToken::from_str(token)
 }
 
+fn parse_direction(suffix: &str) -> Result<Direction, strum::ParseError> {
+    match suffix {
+        "" | "1" => Ok(Direction::Normal),
+        "'" | "3" => Ok(Direction::Prime),
+        "2" | "2'" => Ok(Direction::Double),
+        _ => Err(strum::ParseError::VariantNotFound),
+    }
+}
+
+// On failure, returns the whitespace-separated token that could not be parsed.
+fn parse_moves(scramble: &str, notation: Notation) -> Result<Vec<MoveInstance>, &str> {
+    let re_move = move_regex(notation);
+    let mut parsed = vec![];
+    for token in scramble.split_whitespace() {
+        let mut rest = token;
+        while !rest.is_empty() {
+            let cap = re_move.captures(rest).ok_or(token)?;
+            // tokens hold exactly one move, except in compact notation
+            if notation != Notation::Compact && cap[0].len() != token.len() {
+                return Err(token);
+            }
+            parsed.push(MoveInstance {
+                basemove: parse_base_move(&cap[1]).map_err(|_| token)?,
+                dir: parse_direction(&cap[2]).map_err(|_| token)?,
+            });
+            rest = &rest[cap[0].len()..];
+        }
+    }
+    Ok(parsed)
+}
+
 /// Parses a scramble sequence from a string.
 ///
 /// Returns a Result object indicating whether the parse was successful.
 pub fn parse_scramble(scramble: &str) -> Result<Vec<MoveInstance>, strum::ParseError> {
-    let mut parsed = vec![];
-    let re_normal = Regex::new(r"^([UDLRFB])$").unwrap();
-    let re_prime = Regex::new(r"^([UDLRFB])'").unwrap();
-    let re_double = Regex::new(r"^([UDLRFB])2").unwrap();
-    let mut parse_error = "";
-    'tokens: for token in scramble.split_whitespace() {
-        if re_normal.is_match(token) {
-            for cap in re_normal.captures_iter(token) {
-                let basemove = parse_base_move(&cap[1])?;
-                parsed.push(MoveInstance {
-                    basemove,
-                    dir: Direction::Normal,
-                })
+    parse_scramble_with(scramble, Notation::Singmaster)
+        .map_err(|_| strum::ParseError::VariantNotFound)
+}
+
+/// Parses a scramble sequence written in the given notation, such as one
+/// produced by `MoveSequence::display`.
+///
+/// Returns a Result object indicating whether the parse was successful, with
+/// the first token that is not a move on failure.
+pub fn parse_scramble_with(
+    scramble: &str,
+    notation: Notation,
+) -> Result<Vec<MoveInstance>, InvalidMove> {
+    parse_moves(scramble, notation).map_err(|token| InvalidMove {
+        token: token.to_string(),
+    })
+}
+
+/// A token that is not a move in the expected notation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidMove {
+    pub token: String,
+}
+
+impl std::fmt::Display for InvalidMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid move `{}`", self.token)
+    }
+}
+
+impl std::error::Error for InvalidMove {}
+
+/// An error encountered while parsing scrambles from a reader.
+#[derive(Debug)]
+pub enum ParseError {
+    /// The given (1-based) line contained a token that is not a move in the
+    /// expected notation.
+    InvalidToken { line: usize, token: String },
+    /// Reading the given (1-based) line failed.
+    Io { line: usize, source: std::io::Error },
+}
+
+impl ParseError {
+    /// The (1-based) line number the error occurred on.
+    pub fn line(&self) -> usize {
+        match self {
+            ParseError::InvalidToken { line, .. } | ParseError::Io { line, .. } => *line,
+        }
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::InvalidToken { line, token } => {
+                write!(f, "line {}: invalid move `{}`", line, token)
             }
-        } else if re_prime.is_match(token) {
-            for cap in re_prime.captures_iter(token) {
-                let basemove = parse_base_move(&cap[1])?;
-                parsed.push(MoveInstance {
-                    basemove,
-                    dir: Direction::Prime,
-                })
+            ParseError::Io { line, source } => write!(f, "line {}: {}", line, source),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::InvalidToken { .. } => None,
+            ParseError::Io { source, .. } => Some(source),
+        }
+    }
+}
+
+/// An iterator over the scrambles in a reader, one per line, returned by
+/// `parse_scrambles`.
+pub struct Scrambles<R> {
+    reader: R,
+    notation: Notation,
+    buf: String,
+    line: usize,
+    // set once reading fails, since a failing reader may keep failing
+    done: bool,
+}
+
+impl<R> Scrambles<R> {
+    /// The (1-based) line number of the most recently returned item, or 0
+    /// if nothing has been read yet.
+    pub fn line_number(&self) -> usize {
+        self.line
+    }
+}
+
+impl<R: BufRead> Iterator for Scrambles<R> {
+    type Item = Result<MoveSequence, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        loop {
+            self.buf.clear();
+            self.line += 1;
+            match self.reader.read_line(&mut self.buf) {
+                Ok(0) => {
+                    self.line -= 1;
+                    return None;
+                }
+                Ok(_) => {}
+                Err(source) => {
+                    self.done = true;
+                    return Some(Err(ParseError::Io {
+                        line: self.line,
+                        source,
+                    }));
+                }
             }
-        } else if re_double.is_match(token) {
-            for cap in re_double.captures_iter(token) {
-                let basemove = parse_base_move(&cap[1])?;
-                parsed.push(MoveInstance {
-                    basemove,
-                    dir: Direction::Double,
-                })
+            if self.buf.trim().is_empty() {
+                continue;
             }
-        } else {
-            parse_error = token;
-            break 'tokens;
+            return Some(
+                parse_moves(&self.buf, self.notation)
+                    .map(MoveSequence::from)
+                    .map_err(|token| ParseError::InvalidToken {
+                        line: self.line,
+                        token: token.to_string(),
+                    }),
+            );
         }
     }
-    if parse_error.is_empty() {
-        Ok(parsed)
-    } else {
-        Err(strum::ParseError::VariantNotFound)
+}
+
+/// Parses one scramble per line from a reader, skipping blank lines.
+///
+/// Lines are parsed lazily, so arbitrarily large scramble files can be
+/// processed without reading them into memory first. The iterator ends after
+/// the first error reading from `reader`, while invalid lines are reported
+/// and skipped.
+///
+/// ```
+/// use rusty_rubik::cube::Notation;
+/// use rusty_rubik::parser::parse_scrambles;
+///
+/// let input = "R U R' U'\n\nF2 B2\n";
+/// let scrambles: Vec<_> = parse_scrambles(input.as_bytes(), Notation::Singmaster)
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(scrambles.len(), 2);
+/// ```
+pub fn parse_scrambles<R: BufRead>(reader: R, notation: Notation) -> Scrambles<R> {
+    Scrambles {
+        reader,
+        notation,
+        buf: String::new(),
+        line: 0,
+        done: false,
     }
 }
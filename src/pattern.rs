@@ -0,0 +1,300 @@
+//! Pattern databases over any subset of the pieces of the cube.
+//!
+//! A `Pattern` picks some of the corners and edges, and whether their
+//! orientations are tracked along with their positions. Every state is
+//! projected onto where those pieces are, and a pattern database records the
+//! distance from each projection to the projections of the goal: either the
+//! states inducing some target cycle types or one exact state, as given by a
+//! `PatternGoal`. Since moves act on the projections as they do on the
+//! states, this distance is a lower bound on the distance of the state
+//! itself, and any number of pattern databases can be combined by taking the
+//! largest bound, as `PruningTables::with_pattern` does.
+//!
+//! ```no_run
+//! use rusty_rubik::pattern::{Pattern, PatternGoal};
+//! use rusty_rubik::pruning::PruningTables;
+//! use rusty_rubik::CycleType;
+//!
+//! let cycle_type: CycleType<u8> = "C:1o,2n,3o E:2o,2o".parse().unwrap();
+//! // the four U edges with their orientations, and the positions alone of
+//! // the four U corners
+//! let pattern = Pattern::new()
+//!     .with_edges(&[0, 1, 2, 3], true)
+//!     .with_corners(&[0, 1, 2, 3], false);
+//! let tables = PruningTables::from(&cycle_type)
+//!     .with_pattern(pattern, &PatternGoal::Targets(cycle_type.into()));
+//! ```
+
+use crate::cube::{self, CubeState};
+use crate::progress::Monitor;
+use crate::pruning::{self, TableError};
+use crate::table::PackedTable;
+use crate::Targets;
+use std::fmt;
+
+/// The pieces tracked by a pattern database.
+///
+/// Corners and edges are numbered as in `cube`. The pieces of each orbit are
+/// kept sorted, so that patterns over the same pieces are equal.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Pattern {
+    corners: Vec<u8>,
+    corner_orientation: bool,
+    edges: Vec<u8>,
+    edge_orientation: bool,
+}
+
+impl Pattern {
+    /// A pattern tracking no pieces, to be extended with `with_corners` and
+    /// `with_edges`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tracks the given corners, and their orientations if `oriented`,
+    /// instead of any corners tracked before.
+    pub fn with_corners(mut self, corners: &[u8], oriented: bool) -> Self {
+        self.corners = corners.to_vec();
+        self.corners.sort_unstable();
+        self.corner_orientation = oriented;
+        self
+    }
+
+    /// Tracks the given edges, and their orientations if `oriented`, instead
+    /// of any edges tracked before.
+    pub fn with_edges(mut self, edges: &[u8], oriented: bool) -> Self {
+        self.edges = edges.to_vec();
+        self.edges.sort_unstable();
+        self.edge_orientation = oriented;
+        self
+    }
+
+    pub fn corners(&self) -> &[u8] {
+        &self.corners
+    }
+
+    pub fn edges(&self) -> &[u8] {
+        &self.edges
+    }
+
+    /// Whether the orientations of the tracked corners are tracked.
+    pub fn corner_orientation(&self) -> bool {
+        self.corner_orientation && !self.corners.is_empty()
+    }
+
+    /// Whether the orientations of the tracked edges are tracked.
+    pub fn edge_orientation(&self) -> bool {
+        self.edge_orientation && !self.edges.is_empty()
+    }
+
+    /// The number of entries in a pattern database over this pattern,
+    /// `8!/(8-j)! * 12!/(12-k)!` for `j` corners and `k` edges, times `3^j`
+    /// if the corner orientations are tracked and `2^k` if the edge
+    /// orientations are.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number doesn't fit in a `usize`, which `validate` rules
+    /// out.
+    pub fn size(&self) -> usize {
+        self.checked_size().expect("pattern database too large")
+    }
+
+    fn checked_size(&self) -> Option<usize> {
+        let corners = cube::partial_size(
+            cube::CORNERS,
+            self.corners.len(),
+            self.corner_orientation().then_some(3),
+        );
+        let edges = cube::partial_size(
+            cube::EDGES,
+            self.edges.len(),
+            self.edge_orientation().then_some(2),
+        );
+        corners.checked_mul(edges)
+    }
+
+    /// Checks that the pattern tracks some pieces, none of them twice, and
+    /// that its database can be indexed, returning
+    /// `TableError::InvalidPattern` otherwise.
+    pub fn validate(&self) -> Result<(), TableError> {
+        let valid = |pieces: &[u8], count: usize| {
+            pieces.windows(2).all(|pair| pair[0] < pair[1])
+                && pieces.last().is_none_or(|&piece| (piece as usize) < count)
+        };
+        if (self.corners.is_empty() && self.edges.is_empty())
+            || !valid(&self.corners, cube::CORNERS)
+            || !valid(&self.edges, cube::EDGES)
+            || self.checked_size().is_none()
+        {
+            return Err(TableError::InvalidPattern(self.clone()));
+        }
+        Ok(())
+    }
+
+    /// The index of a state in a pattern database over this pattern, as
+    /// computed by `CubeState::pattern_index`.
+    pub fn index(&self, state: &CubeState) -> usize {
+        state.pattern_index(self)
+    }
+}
+
+// e.g. `c0-1-2-3n_e0-1-2-3o`, with `o` if the orientations are tracked
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pieces = |pieces: &[u8]| {
+            let pieces: Vec<String> = pieces.iter().map(|piece| piece.to_string()).collect();
+            pieces.join("-")
+        };
+        let orbits = [
+            ("c", &self.corners, self.corner_orientation()),
+            ("e", &self.edges, self.edge_orientation()),
+        ];
+        let mut first = true;
+        for (orbit, tracked, oriented) in orbits {
+            if tracked.is_empty() {
+                continue;
+            }
+            if !first {
+                write!(f, "_")?;
+            }
+            first = false;
+            let orientation = if oriented { "o" } else { "n" };
+            write!(f, "{}{}{}", orbit, pieces(tracked), orientation)?;
+        }
+        Ok(())
+    }
+}
+
+/// What the distances in a pattern database are to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatternGoal {
+    /// The projections of the states inducing any of some targets. The
+    /// database then gives a lower bound for any search towards them.
+    Targets(Targets),
+    /// The projection of one state. The database then only gives a lower
+    /// bound for searches towards that state, such as solving the cube when
+    /// it is the solved state.
+    State(CubeState),
+}
+
+/// A pattern database, giving a lower bound on the number of moves needed
+/// to bring the pieces of its pattern into a goal projection.
+pub struct PatternTable {
+    pattern: Pattern,
+    goal: PatternGoal,
+    table: PackedTable,
+}
+
+impl PatternTable {
+    /// Generates the pattern database over `pattern` towards `goal` in
+    /// memory. See `PruningTables::try_with_pattern` for keeping it with
+    /// the other tables.
+    pub fn generate(pattern: Pattern, goal: PatternGoal) -> Result<Self, TableError> {
+        pattern.validate()?;
+        let header = pruning::pattern_header(&pattern, &goal);
+        let (table, _) = pruning::generate_table(&header, &Monitor::new())?;
+        Ok(Self::from_table(pattern, goal, table))
+    }
+
+    // the pattern database in `table`, indexed as `pattern_header` says
+    pub(crate) fn from_table(pattern: Pattern, goal: PatternGoal, table: PackedTable) -> Self {
+        PatternTable {
+            pattern,
+            goal,
+            table,
+        }
+    }
+
+    pub fn pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
+    pub fn goal(&self) -> &PatternGoal {
+        &self.goal
+    }
+
+    pub fn table(&self) -> &PackedTable {
+        &self.table
+    }
+
+    pub fn get(&self, index: usize) -> u8 {
+        self.table.get(index)
+    }
+
+    /// Computes a lower bound on the number of moves needed to bring the
+    /// tracked pieces of the given state into a goal projection.
+    pub fn compute_h_value(&self, state: &CubeState) -> u8 {
+        self.table.get(state.pattern_index(&self.pattern))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pattern_index() {
+        let pattern = Pattern::new()
+            .with_corners(&[5, 1], true)
+            .with_edges(&[2, 7], false);
+        assert_eq!(pattern.corners(), &[1, 5]);
+        assert_eq!(pattern.size(), 8 * 7 * 9 * 12 * 11);
+        assert_eq!(pattern.to_string(), "c1-5o_e2-7n");
+        for index in (0..pattern.size()).step_by(37) {
+            let state = CubeState::from_pattern_index(&pattern, index);
+            assert_eq!(pattern.index(&state), index);
+        }
+        let mut state = CubeState::default();
+        for m in crate::parser::parse_scramble("R U F' D2 L").unwrap() {
+            let next = state.apply_move_instance(&m);
+            let projected = CubeState::from_pattern_index(&pattern, pattern.index(&state));
+            // moves act on the projections as on the states
+            assert_eq!(
+                pattern.index(&projected.apply_move_instance(&m)),
+                pattern.index(&next)
+            );
+            state = next;
+        }
+
+        assert!(Pattern::new().validate().is_err());
+        assert!(Pattern::new().with_edges(&[3, 3], true).validate().is_err());
+        assert!(Pattern::new().with_corners(&[8], false).validate().is_err());
+        let everything = Pattern::new()
+            .with_corners(&[0, 1, 2, 3, 4, 5, 6, 7], true)
+            .with_edges(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11], true);
+        assert!(everything.validate().is_err());
+    }
+
+    #[test]
+    fn test_pattern_table() {
+        let solved = CubeState::default();
+        let pattern = Pattern::new()
+            .with_corners(&[0, 1], true)
+            .with_edges(&[0], true);
+        let table =
+            PatternTable::generate(pattern.clone(), PatternGoal::State(solved.clone())).unwrap();
+        assert_eq!(table.compute_h_value(&solved), 0);
+        let mut state = solved.clone();
+        for m in crate::parser::parse_scramble("U R").unwrap() {
+            state = state.apply_move_instance(&m);
+        }
+        assert_eq!(table.compute_h_value(&state), 2);
+        let header = pruning::pattern_header(&pattern, table.goal());
+        assert_eq!(pruning::verify_table(&header, table.table()), Ok(()));
+
+        // towards a target, a tracked pair of swapped corners is a goal
+        let targets: Targets = "C:2n E:2n".parse().unwrap();
+        let pattern = Pattern::new()
+            .with_corners(&[0, 1, 2], false)
+            .with_edges(&[0, 1], false);
+        let table = PatternTable::generate(pattern.clone(), PatternGoal::Targets(targets)).unwrap();
+        let header = pruning::pattern_header(&pattern, table.goal());
+        assert_eq!(pruning::verify_table(&header, table.table()), Ok(()));
+        let swapped = CubeState::from_corners([1, 0, 2, 3, 4, 5, 6, 7], [0; cube::CORNERS]);
+        assert_eq!(table.compute_h_value(&swapped), 0);
+        assert_eq!(table.compute_h_value(&solved), 0);
+        let three_cycle = CubeState::from_corners([1, 2, 0, 3, 4, 5, 6, 7], [0; cube::CORNERS]);
+        assert!(table.compute_h_value(&three_cycle) > 0);
+    }
+}
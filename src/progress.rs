@@ -0,0 +1,222 @@
+//! Progress reports and cancellation for long-running work.
+//!
+//! Generating pruning tables can take minutes and a search can take far
+//! longer, so both report their progress to an `Observer` and stop early
+//! once a `CancellationToken` is cancelled. The library itself never prints;
+//! a `Monitor` with no observer discards every report.
+//!
+//! ```
+//! use rusty_rubik::progress::{CancellationToken, Event, Monitor};
+//!
+//! let token = CancellationToken::new();
+//! let monitor = Monitor::new()
+//!     .with_observer(|event: &Event| eprintln!("{}", event))
+//!     .with_cancellation(token.clone());
+//! // from another thread, or a signal handler
+//! token.cancel();
+//! assert!(monitor.is_cancelled());
+//! ```
+
+use std::fmt;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A report on the progress of some work.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Event<'a> {
+    /// Every state of the table named by `tag` up to `depth` has been filled.
+    TableProgress {
+        tag: &'a str,
+        depth: u8,
+        /// The number of entries filled so far.
+        filled: usize,
+        /// The number of entries in the table.
+        len: usize,
+        elapsed: Duration,
+        /// A rough estimate of the time left, from the rate at which entries
+        /// have been filled so far.
+        eta: Option<Duration>,
+    },
+    /// The table named by `tag` has been generated.
+    TableGenerated { tag: &'a str, elapsed: Duration },
+    /// The table at `path`, in the format used before table files had
+    /// headers, is being rewritten in the current format.
+    TableMigrated { path: &'a Path },
+    /// The search is looking for solutions of `depth` moves, having expanded
+    /// `nodes` states so far.
+    SearchProgress {
+        depth: u8,
+        nodes: u64,
+        elapsed: Duration,
+    },
+}
+
+impl fmt::Display for Event<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::TableProgress {
+                tag,
+                depth,
+                filled,
+                len,
+                elapsed,
+                eta,
+            } => {
+                write!(
+                    f,
+                    "{} filled to depth {}: {} of {} entries ({:.4}%) in {:.2?}",
+                    tag,
+                    depth,
+                    filled,
+                    len,
+                    *filled as f64 / *len as f64 * 100.0,
+                    elapsed
+                )?;
+                match eta {
+                    Some(eta) => write!(f, ", about {:.0?} left", eta),
+                    None => Ok(()),
+                }
+            }
+            Event::TableGenerated { tag, elapsed } => {
+                write!(f, "Pruning table {} generated in {:.2?}", tag, elapsed)
+            }
+            Event::TableMigrated { path } => write!(
+                f,
+                "Migrating {} to the current table format...",
+                path.display()
+            ),
+            Event::SearchProgress {
+                depth,
+                nodes,
+                elapsed,
+            } => write!(
+                f,
+                "Searching depth {} ({} nodes in {:.2?})...",
+                depth, nodes, elapsed
+            ),
+        }
+    }
+}
+
+/// Receives the progress reports of some work, possibly from several
+/// threads at once.
+pub trait Observer: Send + Sync {
+    fn observe(&self, event: &Event<'_>);
+}
+
+impl<F: Fn(&Event<'_>) + Send + Sync> Observer for F {
+    fn observe(&self, event: &Event<'_>) {
+        self(event)
+    }
+}
+
+/// A flag asking work to stop, shared by every clone of the token.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks the work watching this token to stop as soon as it can.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The error of work that stopped because it was cancelled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Where some work reports its progress, and how it learns to stop.
+#[derive(Clone, Default)]
+pub struct Monitor {
+    observer: Option<Arc<dyn Observer>>,
+    token: CancellationToken,
+}
+
+impl Monitor {
+    /// A monitor discarding every report, whose work is never cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reports progress to `observer`.
+    pub fn with_observer(mut self, observer: impl Observer + 'static) -> Self {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+
+    /// Stops the work once `token` is cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.token = token;
+        self
+    }
+
+    pub fn report(&self, event: &Event<'_>) {
+        if let Some(observer) = &self.observer {
+            observer.observe(event);
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Fails with `Cancelled` if the work has been cancelled.
+    pub fn check(&self) -> Result<(), Cancelled> {
+        match self.is_cancelled() {
+            true => Err(Cancelled),
+            false => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_monitor() {
+        let seen = Arc::new(Mutex::new(vec![]));
+        let token = CancellationToken::new();
+        let monitor = {
+            let seen = seen.clone();
+            Monitor::new()
+                .with_observer(move |event: &Event| seen.lock().unwrap().push(event.to_string()))
+                .with_cancellation(token.clone())
+        };
+        monitor.report(&Event::TableGenerated {
+            tag: "corners1o2n3o",
+            elapsed: Duration::from_secs(2),
+        });
+        assert_eq!(
+            *seen.lock().unwrap(),
+            ["Pruning table corners1o2n3o generated in 2.00s"]
+        );
+        assert_eq!(monitor.check(), Ok(()));
+        token.cancel();
+        assert_eq!(monitor.clone().check(), Err(Cancelled));
+        Monitor::new().report(&Event::TableGenerated {
+            tag: "corners1o2n3o",
+            elapsed: Duration::ZERO,
+        });
+    }
+}
@@ -4,17 +4,116 @@
 //! moves are needed to solve a given position of the Rubik's Cube. The intention
 //! is for these tables to be pre-generated before any solving work begins.
 //!
-//! Each table is generated by executing an iterative deepening DFS (IDDFS) starting
-//! from the solved state. For each state, the depth is recorded in a vector
-//! of the appropriate size.
+//! Each table is generated by a breadth-first search starting from the goal
+//! states. For each state, the depth is recorded in a `PackedTable` of the
+//! appropriate size.
 
-use itertools::{repeat_n, Itertools};
+use rayon::prelude::*;
 
 use crate::{
     cube::{self, CubeState},
-    CycleType,
+    move_table::{MoveTable, CORNER_ORIENTATIONS, MOVES},
+    pattern::{Pattern, PatternGoal, PatternTable},
+    progress::{Event, Monitor},
+    random::SplitMix64,
+    symmetry::CORNER_SYMMETRIES,
+    table::{self, AtomicPackedTable, PackedTable, Packing, TableStats},
+    table_file::{self, Coordinate, TableFileError, TableHeader},
+    write_cycles, CycleType, Targets,
 };
-use std::{io::Write, time::Instant};
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::RangeBounds;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// What to do about pruning tables that are not on disk.
+///
+/// Generating a table can take minutes and several gigabytes of memory, so
+/// callers that must not block, such as services, should load with
+/// `LoadOnly` and generate tables ahead of time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TablePolicy {
+    /// Only read tables, failing with `TableError::Missing` for a table
+    /// that is not on disk.
+    LoadOnly,
+    /// Read tables, generating and writing those that are not on disk.
+    #[default]
+    LoadOrGenerate,
+    /// Generate and write every table, replacing any on disk.
+    Regenerate,
+}
+
+/// The reasons pruning tables can fail to load or generate.
+#[derive(Debug)]
+pub enum TableError {
+    /// The table is not on disk, and the policy is `TablePolicy::LoadOnly`.
+    Missing(PathBuf),
+    /// The table file exists but could not be read or was refused. It is
+    /// never replaced implicitly; remove it or use `TablePolicy::Regenerate`.
+    Read {
+        path: PathBuf,
+        source: TableFileError,
+    },
+    /// The table was generated but could not be written.
+    Write {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// Some state is too far from the goal states for its depth to be
+    /// packed into the table named by the tag.
+    TooDeep(String),
+    /// An edge subset for a partial edge pattern database is empty or
+    /// contains duplicate or invalid edges.
+    InvalidEdges(Vec<u8>),
+    /// A pattern tracks no pieces, a piece twice or an invalid piece, or has
+    /// too many entries to index.
+    InvalidPattern(Pattern),
+    /// The generation of the table named by the tag was cancelled.
+    Cancelled(String),
+    /// The table doesn't hold the distances to its goal states.
+    Inconsistent {
+        path: PathBuf,
+        inconsistency: Inconsistency,
+    },
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableError::Missing(path) => write!(f, "{} has not been generated", path.display()),
+            TableError::Read { path, source } => {
+                write!(f, "unable to read {}: {}", path.display(), source)
+            }
+            TableError::Write { path, source } => {
+                write!(f, "unable to write {}: {}", path.display(), source)
+            }
+            TableError::TooDeep(tag) => write!(
+                f,
+                "{} has states deeper than {} moves",
+                tag,
+                table::MAX_DEPTH
+            ),
+            TableError::InvalidEdges(edges) => write!(f, "invalid edge subset {:?}", edges),
+            TableError::InvalidPattern(pattern) => write!(f, "invalid pattern {}", pattern),
+            TableError::Cancelled(tag) => write!(f, "generation of {} was cancelled", tag),
+            TableError::Inconsistent {
+                path,
+                inconsistency,
+            } => write!(f, "{} is inconsistent: {}", path.display(), inconsistency),
+        }
+    }
+}
+
+impl std::error::Error for TableError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TableError::Read { source, .. } => Some(source),
+            TableError::Write { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
 
 /**
  * A struct holding pruning information for certain subgroups of the
@@ -25,115 +124,1658 @@ use std::{io::Write, time::Instant};
  * These tables are obtained from `pruning.rs`.
  */
 pub struct PruningTables {
-    /// A pruning table representing the subgroup of corner permutation and orientation.
-    corners: Vec<u8>,
-    // A pruning table representing the subgroup of edge orientation.
-    // pub eo: Vec<u8>,
-    // A pruning table representing the subgroup of edge permutation.
-    // pub ep: Vec<u8>,
+    /// The directory the tables are read from and written to.
+    dir: PathBuf,
+    /// What to do about tables added later that are not on disk.
+    policy: TablePolicy,
+    /// The depth at which the generation of the tables stops, if any.
+    depth_bound: Option<u8>,
+    /// Where the generation of tables added later reports its progress.
+    monitor: Monitor,
+    /// The cycle types the tables were built for, whose goal states end the
+    /// descents that recover exact depths from `Packing::Mod3` tables.
+    targets: Targets,
+    /// A pruning table representing the subgroup of corner permutation and orientation,
+    /// with one entry per symmetry class as indexed by `symmetry::CORNER_SYMMETRIES`.
+    corners: PackedTable,
+    /// A pruning table representing the subgroup of edge orientation, present
+    /// when the target has a nonempty edge partition.
+    eo: Option<PackedTable>,
+    /// A pruning table representing the subgroup of edge permutation, present
+    /// when the target has a nonempty edge partition.
+    ep: Option<PackedTable>,
+    /// Pattern databases over subsets of the edges, added with
+    /// `with_partial_edges`.
+    partial_edges: Vec<PartialEdgeTable>,
+    /// Pattern databases over any pieces, added with `with_pattern`.
+    patterns: Vec<PatternTable>,
+    /// The statistics of each table, by tag, in the order the tables were
+    /// added.
+    stats: Vec<(String, TableStats)>,
+}
+
+/// The lower bound given by `PruningTables` for a state, along with the
+/// exact depths of the state in the tables that may be stored modulo 3.
+///
+/// Passing the `HValue` of a state to `PruningTables::h_value_from_parent`
+/// lets the depths of its neighbours be recovered with a single lookup each.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HValue {
+    /// A lower bound on the number of moves needed to solve the state.
+    pub h: u8,
+    corners: u8,
+    ep: u8,
+}
+
+/// A pattern database tracking the positions and orientations of a subset
+/// of the edges.
+///
+/// Its goal states are the placements of those edges that occur in some
+/// state inducing the target edge partition, so it gives a lower bound for
+/// the full target while needing only `12!/(12-k)! * 2^k` entries for `k`
+/// edges.
+pub struct PartialEdgeTable {
+    edges: Vec<u8>,
+    table: PackedTable,
+}
+
+impl PartialEdgeTable {
+    /// The edges tracked by this table, in the order used for indexing.
+    pub fn edges(&self) -> &[u8] {
+        &self.edges
+    }
+
+    pub fn get(&self, index: usize) -> u8 {
+        self.table.get(index)
+    }
+
+    /// Computes a lower bound on the number of moves needed to bring the
+    /// tracked edges of the given state into a goal placement.
+    pub fn compute_h_value(&self, state: &CubeState) -> u8 {
+        self.table.get(state.partial_edge_index(&self.edges))
+    }
+}
+
+/// Returns the tag naming the corner pruning table for some targets, such
+/// as `corners1o2n3o`. The table is stored in `<tag>.pt`.
+///
+/// Only the corner partitions are used, in canonical order, so targets that
+/// share their corner partitions share a table. Targets with several corner
+/// partitions are named by a checksum of those partitions instead, as in
+/// `corners_any5cc5000f` for `C:1o,7o | C:3o,5o`.
+pub fn corners_tag(targets: impl Into<Targets>) -> String {
+    let corners = targets.into().corners();
+    match corners.iter().next() {
+        Some(cycle_type) if corners.len() == 1 => {
+            partition_tag("corners", &cycle_type.corner_partition)
+        }
+        _ => union_tag("corners", &corners),
+    }
+}
+
+/// Returns the tag naming the edge pruning tables for some targets, such as
+/// `edges2o2o`, and named as in `corners_tag` for several edge partitions.
+/// The edge orientation and edge permutation tables are stored in
+/// `<tag>_o.pt` and `<tag>_p.pt`.
+pub fn edges_tag(targets: impl Into<Targets>) -> String {
+    let edges = targets.into().edges();
+    match edges.iter().next() {
+        Some(cycle_type) if edges.len() == 1 => partition_tag("edges", &cycle_type.edge_partition),
+        _ => union_tag("edges", &edges),
+    }
+}
+
+/// Returns the tag naming the partial edge pattern database over `edges`
+/// for some targets, such as `edges2o2o_e0-1-2-3`. The table is stored in
+/// `<tag>.pt`.
+pub fn partial_edges_tag(targets: impl Into<Targets>, edges: &[u8]) -> String {
+    let edges: Vec<String> = edges.iter().map(|edge| edge.to_string()).collect();
+    format!("{}_e{}", edges_tag(targets), edges.join("-"))
+}
+
+// the tag of the partitions of several targets, which would be too long to
+// spell out
+fn union_tag(orbit: &str, targets: &Targets) -> String {
+    let checksum = crc32fast::hash(targets.to_string().as_bytes());
+    format!("{}_any{:08x}", orbit, checksum)
+}
+
+fn partition_tag(orbit: &str, partition: &[(u8, bool)]) -> String {
+    let mut tag = orbit.to_string();
+    write_cycles(&mut tag, partition, "").expect("writing to a String cannot fail");
+    tag
 }
 
 impl PruningTables {
-    /// Reads the default pruning tables from the default
-    /// file names.
-    pub fn from(tag: &str, cycle_type: &CycleType<u8>) -> Self {
-        let corners = std::fs::read(tag.to_string() + ".pt")
-            .unwrap_or_else(|_| generate_pruning_table_corners(tag, cycle_type));
-        // let edges_o = std::fs::read("edges_o.pt").unwrap();
-        // let edges_p = std::fs::read("edges_p.pt").unwrap();
-        PruningTables {
+    /// Reads the pruning tables for a cycle type, or for any set of `Targets`,
+    /// from the file names given by `corners_tag` and `edges_tag` in the
+    /// current directory, generating them if they don't exist.
+    ///
+    /// Edge tables are only used when some target has a nonempty edge
+    /// partition. See `load` for control over where the tables are kept and
+    /// whether they are generated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a table can't be read, generated or written.
+    pub fn from(targets: impl Into<Targets>) -> Self {
+        Self::from_with_packing(targets, Packing::Nibble)
+    }
+
+    /// Like `from`, but keeps the corner and edge permutation tables in
+    /// memory with the given packing.
+    ///
+    /// With `Packing::Mod3` these tables take half the memory, and exact
+    /// depths are recovered during search from the depths of the parent
+    /// state, or by descending to a goal state for a fresh state. They are
+    /// also generated and stored that way, in files named as in `table_tag`,
+    /// so the full tables never have to fit in memory. The much smaller edge
+    /// orientation and partial edge tables are always exact.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a table can't be read, generated or written.
+    pub fn from_with_packing(targets: impl Into<Targets>, packing: Packing) -> Self {
+        Self::load(".", targets, packing, TablePolicy::LoadOrGenerate)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Reads the pruning tables for some targets from `dir`, which is
+    /// created if a table needs to be written, generating the tables
+    /// according to `policy`. The packing is as in `from_with_packing`.
+    pub fn load(
+        dir: impl AsRef<Path>,
+        targets: impl Into<Targets>,
+        packing: Packing,
+        policy: TablePolicy,
+    ) -> Result<Self, TableError> {
+        Self::load_bounded(dir, targets, packing, policy, None)
+    }
+
+    /// Like `load`, but with depth-bounded tables if `depth_bound` is some
+    /// `k`: their generation stops at depth `k`, and every deeper state is
+    /// given the bound `k + 1`. Such tables are still admissible and are
+    /// much faster to generate, which suits quick approximate searches.
+    ///
+    /// Depth-bounded tables are always kept with `Packing::Nibble`, since
+    /// the exact depth of a state at the bound can't be recovered from a
+    /// `Packing::Mod3` entry.
+    pub fn load_bounded(
+        dir: impl AsRef<Path>,
+        targets: impl Into<Targets>,
+        packing: Packing,
+        policy: TablePolicy,
+        depth_bound: Option<u8>,
+    ) -> Result<Self, TableError> {
+        Self::load_monitored(dir, targets, packing, policy, depth_bound, Monitor::new())
+    }
+
+    /// Like `load_bounded`, but reports the progress of generating tables,
+    /// including those added later, to `monitor`, which can also cancel it.
+    pub fn load_monitored(
+        dir: impl AsRef<Path>,
+        targets: impl Into<Targets>,
+        packing: Packing,
+        policy: TablePolicy,
+        depth_bound: Option<u8>,
+        monitor: Monitor,
+    ) -> Result<Self, TableError> {
+        let dir = dir.as_ref();
+        let targets = targets.into();
+        // the corner and edge permutation tables are generated and stored
+        // with the packing they are kept in
+        let pack = |header: TableHeader| match depth_bound {
+            None => TableHeader { packing, ..header },
+            Some(_) => header,
+        };
+        let mut stats = vec![];
+        let mut load = |header: TableHeader| {
+            let header = header.with_depth_bound(depth_bound);
+            let (table, table_stats) = load_or_generate(dir, policy, &header, &monitor)?;
+            stats.push((table_tag(&header), table_stats));
+            Ok::<_, TableError>(table)
+        };
+        let corners = load(pack(corners_header(&targets)))?;
+        let (eo, ep) = if !targets.has_edges() {
+            (None, None)
+        } else {
+            let eo = load(edges_o_header(&targets))?;
+            let ep = load(pack(edges_p_header(&targets)))?;
+            (Some(eo), Some(ep))
+        };
+        Ok(PruningTables {
+            dir: dir.to_path_buf(),
+            policy,
+            depth_bound,
+            monitor,
+            targets,
             corners,
-            // eo: edges_o,
-            // ep: edges_p,
+            eo,
+            ep,
+            partial_edges: vec![],
+            patterns: vec![],
+            stats,
+        })
+    }
+
+    /// Adds a pattern database over the given subset of the edges (numbered
+    /// as in `cube`) towards the targets of the tables, reading it from the file named by `partial_edges_tag`
+    /// in the directory of the other tables or generating it if it doesn't
+    /// exist. Several databases over disjoint or overlapping subsets can be
+    /// added.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `edges` is empty or contains duplicate or invalid edges, or
+    /// if the table can't be read, generated or written.
+    pub fn with_partial_edges(self, edges: &[u8]) -> Self {
+        self.try_with_partial_edges(edges)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like `with_partial_edges`, but follows the policy, depth bound and
+    /// monitor the other tables were loaded with and returns an error instead of
+    /// panicking.
+    pub fn try_with_partial_edges(mut self, edges: &[u8]) -> Result<Self, TableError> {
+        let mut sorted = edges.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        if sorted.is_empty()
+            || sorted.len() != edges.len()
+            || sorted[sorted.len() - 1] >= cube::EDGES as u8
+        {
+            return Err(TableError::InvalidEdges(edges.to_vec()));
         }
+        let header =
+            partial_edges_header(&self.targets, &sorted).with_depth_bound(self.depth_bound);
+        let (table, stats) = load_or_generate(&self.dir, self.policy, &header, &self.monitor)?;
+        self.stats.push((table_tag(&header), stats));
+        self.partial_edges.push(PartialEdgeTable {
+            edges: sorted,
+            table,
+        });
+        Ok(self)
+    }
+
+    pub fn partial_edges(&self) -> &[PartialEdgeTable] {
+        &self.partial_edges
+    }
+
+    /// Adds the pattern database over `pattern` towards `goal`, reading it
+    /// from the file named by `pattern_tag` in the directory of the other
+    /// tables or generating it if it doesn't exist. The goal should be
+    /// reached by every goal state of the search the tables are used for.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pattern is invalid, as checked by `Pattern::validate`,
+    /// or if the table can't be read, generated or written.
+    pub fn with_pattern(self, pattern: Pattern, goal: &PatternGoal) -> Self {
+        self.try_with_pattern(pattern, goal)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like `with_pattern`, but follows the policy, depth bound and monitor
+    /// the other tables were loaded with and returns an error instead of panicking.
+    pub fn try_with_pattern(
+        mut self,
+        pattern: Pattern,
+        goal: &PatternGoal,
+    ) -> Result<Self, TableError> {
+        pattern.validate()?;
+        let header = pattern_header(&pattern, goal).with_depth_bound(self.depth_bound);
+        let (table, stats) = load_or_generate(&self.dir, self.policy, &header, &self.monitor)?;
+        self.stats.push((table_tag(&header), stats));
+        self.patterns
+            .push(PatternTable::from_table(pattern, goal.clone(), table));
+        Ok(self)
     }
 
+    /// Adds a pattern database that was built separately, such as with
+    /// `PatternTable::generate`.
+    pub fn with_pattern_table(mut self, table: PatternTable) -> Self {
+        self.patterns.push(table);
+        self
+    }
+
+    pub fn patterns(&self) -> &[PatternTable] {
+        &self.patterns
+    }
+
+    /// The statistics of each table, by the tag naming it, in the order the
+    /// tables were added. They are computed when a table is generated and
+    /// stored with it.
+    pub fn stats(&self) -> &[(String, TableStats)] {
+        &self.stats
+    }
+
+    /// The directory the tables are read from and written to.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The targets the tables were built for.
+    pub fn targets(&self) -> &Targets {
+        &self.targets
+    }
+
+    /// The depth at which the generation of the tables stops, if they are
+    /// depth-bounded.
+    pub fn depth_bound(&self) -> Option<u8> {
+        self.depth_bound
+    }
+
+    /// Checks each table with `verify_table`. Their checksums were already
+    /// checked when they were read, unless they were memory-mapped.
+    pub fn verify(&self) -> Result<(), TableError> {
+        let targets = &self.targets;
+        let mut tables = vec![(corners_header(targets), &self.corners)];
+        if let (Some(eo), Some(ep)) = (&self.eo, &self.ep) {
+            tables.push((edges_o_header(targets), eo));
+            tables.push((edges_p_header(targets), ep));
+        }
+        for partial_edges in self.partial_edges.iter() {
+            tables.push((
+                partial_edges_header(targets, &partial_edges.edges),
+                &partial_edges.table,
+            ));
+        }
+        let mut patterns = vec![];
+        for pattern in self.patterns.iter() {
+            let header = pattern_header(pattern.pattern(), pattern.goal());
+            patterns.push((header, pattern.table()));
+        }
+        for (header, table) in tables.into_iter().chain(patterns) {
+            let header = TableHeader {
+                packing: table.packing(),
+                ..header.with_depth_bound(self.depth_bound)
+            };
+            verify_table(&header, table).map_err(|inconsistency| TableError::Inconsistent {
+                path: table_path(&self.dir, &table_tag(&header)),
+                inconsistency,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// The packing of the corner and edge permutation tables.
+    pub fn packing(&self) -> Packing {
+        self.corners.packing()
+    }
+
+    /// Returns the exact depth of the corner state with the given index, as
+    /// computed by `CubeState::corner_state_index`.
     pub fn corners(&self, index: usize) -> u8 {
-        self.corners[index]
+        self.corner_depth(&CubeState::from_corner_state_index(index as u32))
+    }
+
+    /// Returns the exact number of moves needed to bring the corners of a
+    /// state into a goal of the targets, regardless of the edges. Beyond the
+    /// depth bound `k` of a depth-bounded table, this is `k + 1`, and if no
+    /// goal can be reached, it is `table::UNFILLED`.
+    pub fn corner_depth(&self, state: &CubeState) -> u8 {
+        let mut multi_bv = vec![0_u8; cube::CORNERS];
+        exact_depth(
+            &self.corners,
+            state,
+            |state| CORNER_SYMMETRIES.sym_coordinate(state),
+            |state| {
+                self.targets
+                    .iter()
+                    .any(|cycle_type| state.induces_corner_cycle_type(cycle_type, &mut multi_bv))
+            },
+        )
+    }
+
+    /// Like `corner_depth`, but for a state one move away from a state whose
+    /// corners are `parent_depth` moves from a goal, which takes a single
+    /// lookup even in a `Packing::Mod3` table.
+    pub fn corner_depth_from_parent(&self, state: &CubeState, parent_depth: u8) -> u8 {
+        depth_from_parent(
+            &self.corners,
+            CORNER_SYMMETRIES.sym_coordinate(state),
+            parent_depth,
+        )
+    }
+
+    /// Iterates over the indices, as computed by
+    /// `CubeState::corner_state_index`, of the corner states whose corners
+    /// are a number of moves from a goal within `depths`, such as `11..=11`
+    /// or `9..`, in increasing order. Beyond the depth bound `k` of
+    /// depth-bounded tables, every state is at depth `k + 1`.
+    ///
+    /// Every one of the `CORNER_STATES` corner states is looked up, which
+    /// takes seconds; `random_corner_states` draws a few of them quickly.
+    /// With `Packing::Mod3`, the exact depths of the whole corner table are
+    /// first recovered, which takes longer.
+    ///
+    /// ```no_run
+    /// use rusty_rubik::pruning::PruningTables;
+    /// use rusty_rubik::CycleType;
+    ///
+    /// let cycle_type: CycleType<u8> = "C:1o,2n,3o".parse().unwrap();
+    /// let tables = PruningTables::from(&cycle_type);
+    /// // the hardest corner states for the cycle type
+    /// let deepest = tables.corner_stats().max_depth().unwrap();
+    /// for index in tables.corner_indices_at(deepest..=deepest).take(10) {
+    ///     assert_eq!(tables.corners(index as usize), deepest);
+    /// }
+    /// ```
+    pub fn corner_indices_at<'a>(
+        &'a self,
+        depths: impl RangeBounds<u8> + Sync + 'a,
+    ) -> impl Iterator<Item = u32> + 'a {
+        let symmetries = &*CORNER_SYMMETRIES;
+        let table = self.exact_corners();
+        // only the corner permutations of classes with some entry within
+        // `depths` are looked up, in parallel, a chunk at a time
+        let matching: Vec<bool> = (0..symmetries.classes())
+            .into_par_iter()
+            .map(|class| {
+                let entries = class * CORNER_ORIENTATIONS..(class + 1) * CORNER_ORIENTATIONS;
+                entries
+                    .into_iter()
+                    .any(|index| depths.contains(&table.get(index)))
+            })
+            .collect();
+        let permutations = CORNER_STATES / CORNER_ORIENTATIONS;
+        (0..permutations)
+            .step_by(LOOKUP_CHUNK)
+            .flat_map(move |start| {
+                let end = permutations.min(start + LOOKUP_CHUNK);
+                (start..end)
+                    .into_par_iter()
+                    .flat_map_iter(|cp_index| {
+                        let first = (cp_index * CORNER_ORIENTATIONS) as u32;
+                        let state = CubeState::from_corner_state_index(first);
+                        let (class, _) = symmetries.class_and_symmetry(&state);
+                        let orientations = match matching[class] {
+                            true => 0..CORNER_ORIENTATIONS as u32,
+                            false => 0..0,
+                        };
+                        orientations.map(move |co_index| first + co_index)
+                    })
+                    .filter(|&index| {
+                        let state = CubeState::from_corner_state_index(index);
+                        depths.contains(&table.get(symmetries.sym_coordinate(&state)))
+                    })
+                    .collect::<Vec<u32>>()
+            })
+    }
+
+    /// Like `corner_indices_at`, but iterates over the corner states
+    /// themselves, with their edges solved.
+    pub fn corner_states_at<'a>(
+        &'a self,
+        depths: impl RangeBounds<u8> + Sync + 'a,
+    ) -> impl Iterator<Item = CubeState> + 'a {
+        self.corner_indices_at(depths)
+            .map(CubeState::from_corner_state_index)
+    }
+
+    /// The number of corner states whose corners are a number of moves from
+    /// a goal within `depths`, as `corner_indices_at` would count them.
+    pub fn corner_count_at(&self, depths: impl RangeBounds<u8>) -> u64 {
+        self.corner_stats()
+            .histogram
+            .iter()
+            .enumerate()
+            .filter(|&(depth, _)| depths.contains(&(depth as u8)))
+            .map(|(_, &count)| count)
+            .sum()
+    }
+
+    /// Draws corner states uniformly at random, and independently of each
+    /// other, from those whose corners are a number of moves from a goal
+    /// within `depths`, with their edges solved. The same seed draws the
+    /// same states. There are none if no corner state is within `depths`.
+    ///
+    /// Each draw takes about `CORNER_STATES / corner_count_at(depths)`
+    /// lookups.
+    pub fn random_corner_states<'a>(
+        &'a self,
+        depths: impl RangeBounds<u8> + 'a,
+        rng: &'a mut SplitMix64,
+    ) -> impl Iterator<Item = CubeState> + 'a {
+        let table = self.exact_corners();
+        let empty = self.corner_count_at((depths.start_bound(), depths.end_bound())) == 0;
+        std::iter::from_fn(move || {
+            if empty {
+                return None;
+            }
+            loop {
+                let index = rng.below(CORNER_STATES as u64) as u32;
+                let state = CubeState::from_corner_state_index(index);
+                if depths.contains(&table.get(CORNER_SYMMETRIES.sym_coordinate(&state))) {
+                    return Some(state);
+                }
+            }
+        })
+    }
+
+    /// The statistics of the corner table, the first of `stats`.
+    pub fn corner_stats(&self) -> &TableStats {
+        &self.stats[0].1
+    }
+
+    // the exact depth of every entry of the corner table, which is the table
+    // itself unless it only stores depths modulo 3
+    fn exact_corners(&self) -> Cow<'_, PackedTable> {
+        if self.corners.packing() == Packing::Nibble {
+            return Cow::Borrowed(&self.corners);
+        }
+        let unfilled = self.corners.packing().unfilled();
+        let depths: Vec<u8> = (0..self.corners.len())
+            .into_par_iter()
+            .map(|index| match self.corners.get(index) {
+                entry if entry == unfilled => table::UNFILLED,
+                _ => self.corner_depth(&CORNER_SYMMETRIES.representative(index)),
+            })
+            .collect();
+        Cow::Owned(PackedTable::from_unpacked(&depths))
+    }
+
+    fn ep_depth(&self, ep: &PackedTable, state: &CubeState) -> u8 {
+        let mut multi_bv = vec![0_u8; cube::EDGES];
+        exact_depth(
+            ep,
+            state,
+            |state| state.edge_permutation_index() as usize,
+            |state| {
+                self.targets
+                    .iter()
+                    .any(|cycle_type| state.admits_edge_permutation(cycle_type, &mut multi_bv))
+            },
+        )
+    }
+
+    /// Computes the `HValue` of a state without knowing any of its
+    /// neighbours, which takes a descent through each `Packing::Mod3` table.
+    pub fn h_value(&self, state: &CubeState) -> HValue {
+        let corners = self.corner_depth(state);
+        let ep = self.ep.as_ref().map_or(0, |ep| self.ep_depth(ep, state));
+        self.combine(state, corners, ep)
+    }
+
+    /// Computes the `HValue` of a state from the `HValue` of a state one
+    /// move away, as during search.
+    pub fn h_value_from_parent(&self, state: &CubeState, parent: HValue) -> HValue {
+        let corners = self.corner_depth_from_parent(state, parent.corners);
+        let ep = self.ep.as_ref().map_or(0, |ep| {
+            depth_from_parent(ep, state.edge_permutation_index() as usize, parent.ep)
+        });
+        self.combine(state, corners, ep)
+    }
+
+    fn combine(&self, state: &CubeState, corners: u8, ep: u8) -> HValue {
+        let mut h = corners.max(ep);
+        if let Some(eo) = &self.eo {
+            h = h.max(eo.get(state.edge_orientation_index() as usize));
+        }
+        for partial_edges in self.partial_edges.iter() {
+            h = h.max(partial_edges.compute_h_value(state));
+        }
+        for pattern in self.patterns.iter() {
+            h = h.max(pattern.compute_h_value(state));
+        }
+        HValue { h, corners, ep }
     }
 
     /// Computes a lower bound on the number of moves needed to
     /// solve the given state, based on the pruning table values.
     pub fn compute_h_value(&self, state: &CubeState) -> u8 {
-        let corners = state.corner_state_index();
-        // let (corners, eo, ep) = state.state_index();
-        // std::cmp::max(
-        //     self.corners[corners as usize],
-        //     std::cmp::max(self.eo[eo as usize], self.ep[ep as usize]),
-        // )
-        self.corners[corners as usize]
+        self.h_value(state).h
     }
 }
 
-pub fn write_table(table: &[u8], filename: String) {
-    let mut file = std::fs::File::create(filename).expect("Unable to create file.");
-    file.write_all(table).expect("Unable to write to file.");
+// Returns the exact depth of a state in a table, or `table::UNFILLED` if no
+// goal state can be reached from it. For a `Packing::Mod3` table this walks
+// to a goal state, each step moving to a neighbour one closer.
+fn exact_depth(
+    table: &PackedTable,
+    state: &CubeState,
+    index: impl Fn(&CubeState) -> usize,
+    mut is_goal: impl FnMut(&CubeState) -> bool,
+) -> u8 {
+    let mut residue = table.get(index(state));
+    if residue == table.packing().unfilled() {
+        return table::UNFILLED;
+    }
+    if table.packing() == Packing::Nibble {
+        return residue;
+    }
+    let mut state = state.clone();
+    let mut depth = 0;
+    while !is_goal(&state) {
+        residue = (residue + 2) % 3;
+        state = cube::ALL_MOVES
+            .iter()
+            .map(|m| state.apply_move_instance(m))
+            .find(|next| table.get(index(next)) == residue)
+            .expect("a state off the goal has a neighbour closer to it");
+        depth += 1;
+    }
+    depth
 }
 
-/// Generates a pruning table for the corners of a Rubik's Cube.
-pub fn generate_pruning_table_corners(tag: &str, cycle_type: &CycleType<u8>) -> Vec<u8> {
-    let now = Instant::now();
+// Returns the exact depth of the entry at `index` of a table, which is one
+// move from a state at `parent_depth`, or `table::UNFILLED` if no goal state
+// can be reached from it.
+fn depth_from_parent(table: &PackedTable, index: usize, parent_depth: u8) -> u8 {
+    let entry = table.get(index);
+    match table.packing() {
+        _ if entry == table.packing().unfilled() => table::UNFILLED,
+        Packing::Nibble => entry,
+        Packing::Mod3 => table::depth_from_neighbour(entry, parent_depth),
+    }
+}
 
-    let cubies: u8 = 8;
-    let orientation_count: i8 = 3;
-    let mut table = vec![255_u8; 88179840];
+// How many corner permutations, with all their orientations, are looked up
+// in parallel at a time when enumerating corner states by depth.
+const LOOKUP_CHUNK: usize = 1 << 5;
 
-    let mut depth = 0;
-    let mut remaining = table.len();
-    let mut multi_bv = vec![0_u8; cube::CORNERS.max(cube::EDGES)];
-    loop {
-        println!("Building {} pruning table for depth {}...", tag, depth);
-        for (cp_index, cp) in (0..cubies).permutations(cubies as usize).enumerate() {
-            for (co_index, co) in repeat_n(0..orientation_count, cubies as usize)
-                .multi_cartesian_product()
-                // TODO more efficient way than filtering
-                .filter(|p| p.iter().sum::<i8>().rem_euclid(orientation_count) == 0)
-                .enumerate()
+/// The number of corner states, 8! * 3^7. Corner pruning tables only store
+/// one entry per symmetry class, `symmetry::CORNER_SYMMETRIES.len()`.
+pub const CORNER_STATES: usize = 88179840;
+/// The number of entries in an edge orientation pruning table, 2^11.
+pub const EDGE_ORIENTATIONS: usize = 2048;
+/// The number of entries in an edge permutation pruning table, 12!.
+pub const EDGE_PERMUTATIONS: usize = 479001600;
+
+/// The number of entries in a partial edge pattern database over `k` edges,
+/// 12!/(12-k)! * 2^k.
+pub fn partial_edges_size(k: usize) -> usize {
+    (cube::EDGES + 1 - k..=cube::EDGES).product::<usize>() << k
+}
+
+/// The header of the corner pruning table for some targets.
+pub fn corners_header(targets: impl Into<Targets>) -> TableHeader {
+    TableHeader {
+        coordinate: Coordinate::CornerSymmetry,
+        targets: targets.into().corners(),
+        packing: Packing::Nibble,
+        depth_bound: None,
+        len: CORNER_SYMMETRIES.len(),
+    }
+}
+
+// the header of an edge table, which only depends on the edge partitions
+fn edges_header(targets: impl Into<Targets>, coordinate: Coordinate, len: usize) -> TableHeader {
+    TableHeader {
+        coordinate,
+        targets: targets.into().edges(),
+        packing: Packing::Nibble,
+        depth_bound: None,
+        len,
+    }
+}
+
+/// The header of the edge orientation pruning table for some targets.
+pub fn edges_o_header(targets: impl Into<Targets>) -> TableHeader {
+    edges_header(targets, Coordinate::EdgeOrientation, EDGE_ORIENTATIONS)
+}
+
+/// The header of the edge permutation pruning table for some targets.
+pub fn edges_p_header(targets: impl Into<Targets>) -> TableHeader {
+    edges_header(targets, Coordinate::EdgePermutation, EDGE_PERMUTATIONS)
+}
+
+/// The header of the partial edge pattern database over `edges` for some
+/// targets.
+pub fn partial_edges_header(targets: impl Into<Targets>, edges: &[u8]) -> TableHeader {
+    edges_header(
+        targets,
+        Coordinate::PartialEdges(edges.to_vec()),
+        partial_edges_size(edges.len()),
+    )
+}
+
+/// The header of the pattern database over `pattern` towards `goal`. For
+/// targets, only the partitions of the orbits the pattern tracks are kept.
+pub fn pattern_header(pattern: &Pattern, goal: &PatternGoal) -> TableHeader {
+    let (targets, target) = match goal {
+        PatternGoal::Targets(targets) => {
+            let tracked = |orbit: &[u8], partition: &Vec<(u8, bool)>| match orbit {
+                [] => vec![],
+                _ => partition.clone(),
+            };
+            let targets = targets.iter().map(|cycle_type| CycleType {
+                corner_partition: tracked(pattern.corners(), &cycle_type.corner_partition),
+                edge_partition: tracked(pattern.edges(), &cycle_type.edge_partition),
+            });
+            (Targets::new(targets), None)
+        }
+        PatternGoal::State(state) => (
+            Targets::from(CycleType::default()),
+            Some(state.pattern_index(pattern)),
+        ),
+    };
+    TableHeader {
+        coordinate: Coordinate::Pattern {
+            pattern: pattern.clone(),
+            target,
+        },
+        targets,
+        packing: Packing::Nibble,
+        depth_bound: None,
+        len: pattern.size(),
+    }
+}
+
+/// Returns the tag naming the pattern database over `pattern` for some
+/// targets, or towards the state with the given index if there is a target,
+/// such as `pattern_c0-1n_e0-1-2o_corners2n_edges2o` or
+/// `pattern_c0-1o_e0-1o_s0`. The table is stored in `<tag>.pt`.
+pub fn pattern_tag(targets: &Targets, pattern: &Pattern, target: Option<usize>) -> String {
+    let mut tag = format!("pattern_{}", pattern);
+    match target {
+        Some(target) => tag.push_str(&format!("_s{}", target)),
+        None => {
+            if !pattern.corners().is_empty() {
+                tag = tag + "_" + &corners_tag(targets);
+            }
+            if !pattern.edges().is_empty() {
+                tag = tag + "_" + &edges_tag(targets);
+            }
+        }
+    }
+    tag
+}
+
+/// Returns the tag naming the table described by `header`, as given by
+/// `corners_tag`, `edges_tag`, `partial_edges_tag` or `pattern_tag`. A depth-bounded table
+/// has the bound `k` appended, as in `corners1o2n3o_d5`, and a
+/// `Packing::Mod3` table has `_mod3` appended, as in `corners1o2n3o_mod3`.
+pub fn table_tag(header: &TableHeader) -> String {
+    let targets = &header.targets;
+    let tag = match &header.coordinate {
+        Coordinate::CornerSymmetry => corners_tag(targets),
+        Coordinate::EdgeOrientation => edges_tag(targets) + "_o",
+        Coordinate::EdgePermutation => edges_tag(targets) + "_p",
+        Coordinate::PartialEdges(edges) => partial_edges_tag(targets, edges),
+        Coordinate::Pattern { pattern, target } => pattern_tag(targets, pattern, *target),
+    };
+    let tag = match header.depth_bound {
+        Some(bound) => format!("{}_d{}", tag, bound),
+        None => tag,
+    };
+    match header.packing {
+        Packing::Nibble => tag,
+        Packing::Mod3 => tag + "_mod3",
+    }
+}
+
+/// Returns the path of the table named by `tag` in `dir`, `<dir>/<tag>.pt`.
+pub fn table_path(dir: impl AsRef<Path>, tag: &str) -> PathBuf {
+    dir.as_ref().join(tag.to_string() + ".pt")
+}
+
+/// Writes a table and the statistics of its states to `filename` in the
+/// format of `table_file`, creating its directory if needed.
+pub fn write_table(
+    table: &PackedTable,
+    stats: &TableStats,
+    header: &TableHeader,
+    filename: impl AsRef<Path>,
+) -> Result<(), TableError> {
+    let filename = filename.as_ref();
+    let write = || {
+        if let Some(dir) = filename.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        table_file::write(filename, header, table, stats)
+    };
+    write().map_err(|source| TableError::Write {
+        path: filename.to_path_buf(),
+        source,
+    })
+}
+
+// Reads the table described by `header` from the file named by `table_tag`
+// in `dir`, or generates it and writes it there as `policy` says, returning
+// it with its statistics. Progress is reported to `monitor`.
+fn load_or_generate(
+    dir: &Path,
+    policy: TablePolicy,
+    header: &TableHeader,
+    monitor: &Monitor,
+) -> Result<(PackedTable, TableStats), TableError> {
+    let filename = table_path(dir, &table_tag(header));
+    if policy != TablePolicy::Regenerate {
+        match read_table(&filename, header, policy, monitor)? {
+            Some(loaded) => return Ok(loaded),
+            None if policy == TablePolicy::LoadOnly => return Err(TableError::Missing(filename)),
+            None => {}
+        }
+    }
+    let (table, stats) = generate_table(header, monitor)?;
+    write_table(&table, &stats, header, &filename)?;
+    Ok((table, stats))
+}
+
+/// The statistics of a `Packing::Nibble` table described by `header`, for
+/// files written before the statistics were stored with the table. Without
+/// a symmetry-reduced corner table, they are the histogram of its entries.
+pub fn table_stats(header: &TableHeader, histogram: &[u64], table: &PackedTable) -> TableStats {
+    match header.coordinate {
+        Coordinate::CornerSymmetry => {
+            TableStats::from_weighted(table, |index| states_per_entry(&header.coordinate, index))
+        }
+        _ => TableStats::from_histogram(histogram),
+    }
+}
+
+// The number of states the entry at `index` stands for: the size of its
+// symmetry class in a symmetry-reduced corner table, and otherwise one.
+fn states_per_entry(coordinate: &Coordinate, index: usize) -> u64 {
+    match coordinate {
+        Coordinate::CornerSymmetry => {
+            CORNER_SYMMETRIES.class_size(index / CORNER_ORIENTATIONS) as u64
+        }
+        _ => 1,
+    }
+}
+
+/// Reads the table described by `header` from `filename` along with the
+/// statistics of its states, returning `None` if it doesn't exist. With the
+/// `mmap` feature the table is memory-mapped rather than read, and its
+/// checksum is left to `verify_file`.
+///
+/// Tables written before the current format, as raw bytes with one or two
+/// entries per byte, are read if they have the right size. Unless `policy`
+/// is `TablePolicy::LoadOnly`, which never writes, the file is also migrated
+/// to the current format, which is reported to `monitor`.
+pub fn read_table(
+    filename: &Path,
+    header: &TableHeader,
+    policy: TablePolicy,
+    monitor: &Monitor,
+) -> Result<Option<(PackedTable, TableStats)>, TableError> {
+    let refused = |source| TableError::Read {
+        path: filename.to_path_buf(),
+        source,
+    };
+    match table_file::load(filename, header) {
+        Ok((info, table)) => {
+            let stats = info
+                .stats
+                .unwrap_or_else(|| table_stats(header, &info.histogram, &table));
+            Ok(Some((table, stats)))
+        }
+        Err(TableFileError::NotATable) => {
+            let bytes = std::fs::read(filename).map_err(|err| refused(err.into()))?;
+            let table = if bytes.len() == header.len
+                && header.len != PackedTable::packed_len(header.len, Packing::Nibble)
             {
-                let corner_state_index = cp_index * usize::pow(3, 7) + co_index;
-                if depth == 0 {
-                    if cube::induces_oriented_partition(
-                        &cp,
-                        &co,
-                        &cycle_type.corner_partition,
-                        3,
-                        &mut multi_bv,
-                    ) {
-                        // TODO: IDDFS the first few
-                        table[corner_state_index] = depth;
-                        remaining -= 1;
-                    }
-                } else if table[corner_state_index] == depth - 1 {
-                    let new_state = CubeState::from_corners(
-                        cp.clone().try_into().unwrap(),
-                        co.try_into().unwrap(),
-                    );
-                    for m in cube::ALL_MOVES.iter() {
-                        let new_index =
-                            new_state.apply_move_instance(m).corner_state_index() as usize;
-                        if table[new_index] == 255 {
-                            table[new_index] = depth;
-                            remaining -= 1;
-                        }
-                    }
-                }
+                PackedTable::from_unpacked(&bytes)
+            } else {
+                PackedTable::from_packed(header.len, Packing::Nibble, bytes)
+                    .ok_or_else(|| refused(TableFileError::NotATable))?
+            };
+            let stats = table_stats(header, &table.histogram(), &table);
+            if policy != TablePolicy::LoadOnly {
+                monitor.report(&Event::TableMigrated { path: filename });
+                write_table(&table, &stats, header, filename)?;
             }
+            Ok(Some((table, stats)))
         }
-        println!(
-            "{} entries remaining at depth {} ({:.4}%)",
-            remaining,
+        Err(TableFileError::Io(err)) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(refused(err)),
+    }
+}
+
+/// Runs a breadth-first search outward from the goal states of a subgroup,
+/// recording the depth at which each state is first reached.
+///
+/// The states are identified by their indices alone: `goals` yields the
+/// depth 0 states, possibly more than once, and `neighbours` returns the
+/// indices reached from an index by each move in `cube::ALL_MOVES`, ideally
+/// through `MoveTable`s. Every index of a state must be a neighbour wherever
+/// one of them is, as with the equivalent sym-coordinates of symmetry-reduced
+/// tables.
+///
+/// Early layers are expanded forwards, from each state in the previous layer
+/// to its unfilled neighbours. Once the previous layer is larger than the
+/// unfilled remainder, layers are instead swept backwards, filling each
+/// unfilled state that has a neighbour in the previous layer; this gives the
+/// same layers, since the moves are closed under inverses, but only looks at
+/// the states left. Each layer is expanded in parallel across all cores;
+/// since every state reached from the previous layer gets the same depth
+/// whichever thread reaches it first, the table is identical to one built
+/// serially.
+///
+/// The depths are stored with the packing of the header. With
+/// `Packing::Mod3` the previous layer shares its residue with every third
+/// layer before it, but those states have no unfilled neighbours left, so
+/// the layers are the same while taking half the memory of a
+/// `Packing::Nibble` table.
+///
+/// If the header has a depth bound `k`, the search stops after depth `k` and
+/// every state left is given the depth `k + 1`, a lower bound on its depth.
+///
+/// The progress is reported to `monitor` after each layer. Fails with
+/// `TableError::TooDeep` if some state is deeper than `table::MAX_DEPTH` in
+/// a `Packing::Nibble` table, or if the depth bound is not below it, and with
+/// `TableError::Cancelled` soon after the monitor is cancelled.
+fn generate_pruning_table<N: IntoIterator<Item = usize>>(
+    header: &TableHeader,
+    monitor: &Monitor,
+    goals: impl ParallelIterator<Item = usize>,
+    neighbours: impl Fn(usize) -> N + Sync,
+) -> Result<(PackedTable, TableStats), TableError> {
+    let now = Instant::now();
+    let tag = &table_tag(header);
+    if header
+        .depth_bound
+        .is_some_and(|bound| bound >= table::MAX_DEPTH)
+    {
+        return Err(TableError::TooDeep(tag.clone()));
+    }
+
+    let size = header.len;
+    let packing = header.packing;
+    let unfilled = packing.unfilled();
+    let table = AtomicPackedTable::new(size, packing);
+    let cancelled = || monitor.is_cancelled();
+    // the number of entries filled at a depth, and of the states they stand
+    // for, are summed together
+    let weight = |index: usize| (1, states_per_entry(&header.coordinate, index));
+    let add = |a: (usize, u64), b: (usize, u64)| (a.0 + b.0, a.1 + b.1);
+    let mut stats = TableStats::default();
+    let mut depth = 0;
+    let (mut filled, states) = goals
+        .filter(|&index| !cancelled() && table.fill(index, 0))
+        .map(weight)
+        .reduce(|| (0, 0), add);
+    stats.histogram.push(states);
+    let mut remaining = table.len() - filled;
+    loop {
+        if cancelled() {
+            return Err(TableError::Cancelled(tag.clone()));
+        }
+        let elapsed = now.elapsed();
+        let done = table.len() - remaining;
+        monitor.report(&Event::TableProgress {
+            tag,
             depth,
-            (1.0 - remaining as f64 / table.len() as f64) * 100.0
-        );
-        if remaining == 0 {
+            filled: done,
+            len: table.len(),
+            elapsed,
+            eta: (done > 0).then(|| elapsed.mul_f64(remaining as f64 / done as f64)),
+        });
+        // the rest of the table is unreachable from the goal states, which
+        // only happens when there are none
+        if remaining == 0 || filled == 0 {
             break;
         }
+        if header.depth_bound == Some(depth) {
+            let (_, states) = (0..size)
+                .into_par_iter()
+                .with_min_len(GENERATION_CHUNK)
+                .filter(|&index| table.fill(index, packing.encode(depth + 1)))
+                .map(weight)
+                .reduce(|| (0, 0), add);
+            stats.histogram.push(states);
+            remaining = 0;
+            break;
+        }
+        if packing == Packing::Nibble && depth == table::MAX_DEPTH {
+            return Err(TableError::TooDeep(tag.to_string()));
+        }
         depth += 1;
+        let (previous, current) = (packing.encode(depth - 1), packing.encode(depth));
+        let backward = filled > remaining;
+        let indices = (0..size).into_par_iter().with_min_len(GENERATION_CHUNK);
+        let (layer, states) = if backward {
+            indices
+                .filter(|&index| {
+                    !cancelled()
+                        && table.get(index) == unfilled
+                        && neighbours(index)
+                            .into_iter()
+                            .any(|next| table.get(next) == previous)
+                        && table.fill(index, current)
+                })
+                .map(weight)
+                .reduce(|| (0, 0), add)
+        } else {
+            indices
+                .filter(|&index| !cancelled() && table.get(index) == previous)
+                .map(|index| {
+                    neighbours(index)
+                        .into_iter()
+                        .filter(|&next| table.fill(next, current))
+                        .map(weight)
+                        .fold((0, 0), add)
+                })
+                .reduce(|| (0, 0), add)
+        };
+        filled = layer;
+        if filled > 0 {
+            stats.histogram.push(states);
+        }
+        remaining -= filled;
+    }
+    if remaining > 0 {
+        stats.unreachable = (0..size)
+            .into_par_iter()
+            .with_min_len(GENERATION_CHUNK)
+            .filter(|&index| table.get(index) == unfilled)
+            .map(|index| states_per_entry(&header.coordinate, index))
+            .sum();
+    }
+    if stats.goals() == 0 {
+        stats.histogram.clear();
+    }
+    monitor.report(&Event::TableGenerated {
+        tag,
+        elapsed: now.elapsed(),
+    });
+    Ok((table.into_table(), stats))
+}
+
+// The smallest number of consecutive states handed to one thread while
+// generating a table, so that threads rarely contend for the same bytes.
+const GENERATION_CHUNK: usize = 1 << 12;
+
+// The indices reached from a state by each move, for tables whose
+// coordinates are too large for move tables.
+fn neighbours_of(state: &CubeState, index: impl Fn(&CubeState) -> usize) -> [usize; MOVES] {
+    cube::ALL_MOVES.map(|m| index(&state.apply_move_instance(&m)))
+}
+
+// The neighbours of a sym-coordinate in a symmetry-reduced corner table,
+// together with their equivalents, reached by moving the representative
+// through the corner move tables.
+fn corner_neighbours<'a>(
+    cp: &'a MoveTable,
+    co: &'a MoveTable,
+    index: usize,
+) -> impl Iterator<Item = usize> + 'a {
+    let symmetries = &*CORNER_SYMMETRIES;
+    let (cp_index, co_index) = (
+        symmetries.representative_permutation(index),
+        index % CORNER_ORIENTATIONS,
+    );
+    (0..MOVES).flat_map(move |m| {
+        let next = symmetries.sym_coordinate_of(cp.get(cp_index, m), co.get(co_index, m));
+        std::iter::once(next).chain(symmetries.equivalents(next))
+    })
+}
+
+fn edge_orientation_neighbours() -> impl Fn(usize) -> [usize; MOVES] + Sync {
+    let eo = MoveTable::edge_orientation();
+    move |index| eo.neighbours(index)
+}
+
+// The edge permutation is too large for a move table, so each edge is moved
+// through `MoveTable::edge` instead.
+fn edge_permutation_neighbours(edge: &MoveTable) -> impl Fn(usize) -> [usize; MOVES] + Sync + '_ {
+    move |index| {
+        let ep: [u8; cube::EDGES] = cube::get_permutation_of_index(index as u32);
+        std::array::from_fn(|m| {
+            let mut next = [0; cube::EDGES];
+            for (position, &piece) in ep.iter().enumerate() {
+                next[edge.get(2 * position, m) / 2] = piece;
+            }
+            cube::get_index_of_permutation(&next) as usize
+        })
+    }
+}
+
+fn partial_edge_neighbours<'a>(
+    edge: &'a MoveTable,
+    edges: &'a [u8],
+) -> impl Fn(usize) -> [usize; MOVES] + Sync + 'a {
+    move |index| {
+        let k = edges.len();
+        let placements = cube::placements_of_index(cube::EDGES, k, Some(2), index);
+        std::array::from_fn(|m| {
+            let moved = placements.map(|(position, flip)| {
+                let next = edge.get(2 * position as usize + flip as usize, m);
+                ((next / 2) as u8, (next % 2) as u8)
+            });
+            cube::placements_index(cube::EDGES, &moved[..k], Some(2))
+        })
+    }
+}
+
+fn pattern_neighbours(pattern: &Pattern) -> impl Fn(usize) -> [usize; MOVES] + Sync + '_ {
+    move |index| {
+        neighbours_of(&CubeState::from_pattern_index(pattern, index), |state| {
+            state.pattern_index(pattern)
+        })
+    }
+}
+
+// Whether the entry of a pattern database is a goal: the target if there is
+// one, and otherwise a projection of a state inducing one of the targets.
+fn is_pattern_goal<'a>(
+    pattern: &'a Pattern,
+    target: Option<usize>,
+    targets: &'a Targets,
+) -> impl Fn(usize) -> bool + Sync + 'a {
+    move |index| match target {
+        Some(target) => index == target,
+        None => {
+            let state = CubeState::from_pattern_index(pattern, index);
+            targets
+                .iter()
+                .any(|cycle_type| state.admits_pattern(pattern, cycle_type))
+        }
+    }
+}
+
+// The indices of the permutations of `n` pieces admitting some partition,
+// in increasing order.
+fn permutation_indices_admitting_any<'a>(
+    n: usize,
+    partitions: impl Iterator<Item = &'a Vec<(u8, bool)>>,
+) -> Vec<u32> {
+    let mut indices: Vec<u32> = partitions
+        .flat_map(|partition| cube::permutation_indices_admitting(n, partition))
+        .collect();
+    indices.par_sort_unstable();
+    indices.dedup();
+    indices
+}
+
+/// Generates the table described by `header` with the generator for its
+/// coordinate, such as `generate_pruning_table_corners`, stopping at the
+/// depth bound of the header if it has one, along with the number of states
+/// at each depth. The progress is reported to `monitor`, which can also
+/// cancel the generation.
+pub fn generate_table(
+    header: &TableHeader,
+    monitor: &Monitor,
+) -> Result<(PackedTable, TableStats), TableError> {
+    match &header.coordinate {
+        Coordinate::CornerSymmetry => generate_corners(header, monitor),
+        Coordinate::EdgeOrientation => generate_edges_o(header, monitor),
+        Coordinate::EdgePermutation => generate_edges_p(header, monitor),
+        Coordinate::PartialEdges(edges) => generate_partial_edges(header, monitor, edges),
+        Coordinate::Pattern { pattern, target } => {
+            generate_pattern(header, monitor, pattern, *target)
+        }
+    }
+}
+
+/// Generates a pruning table for the corners of a Rubik's Cube, with one
+/// entry per symmetry class as indexed by
+/// `symmetry::CORNER_SYMMETRIES.sym_coordinate`.
+pub fn generate_pruning_table_corners(
+    targets: impl Into<Targets>,
+) -> Result<PackedTable, TableError> {
+    Ok(generate_corners(&corners_header(targets), &Monitor::new())?.0)
+}
+
+fn generate_corners(
+    header: &TableHeader,
+    monitor: &Monitor,
+) -> Result<(PackedTable, TableStats), TableError> {
+    let symmetries = &*CORNER_SYMMETRIES;
+    let targets = &header.targets;
+    let (cp, co) = (
+        MoveTable::corner_permutation(),
+        MoveTable::corner_orientation(),
+    );
+    generate_pruning_table(
+        header,
+        monitor,
+        // goal states are closed under symmetry, so every class of them has
+        // a member whose corner permutation is a representative; only the
+        // orientations of representatives that admit a partition are tried
+        permutation_indices_admitting_any(
+            cube::CORNERS,
+            targets
+                .iter()
+                .map(|cycle_type| &cycle_type.corner_partition),
+        )
+        .into_par_iter()
+        .map(|cp_index| CubeState::from_corner_state_index(cp_index * CORNER_ORIENTATIONS as u32))
+        .filter(|state| symmetries.is_representative(state))
+        .flat_map_iter(|state| {
+            let (class, _) = symmetries.class_and_symmetry(&state);
+            let cp_index = state.corner_permutation_index();
+            let mut multi_bv = [0; cube::CORNERS];
+            (0..CORNER_ORIENTATIONS).filter_map(move |co_index| {
+                let index = cp_index as usize * CORNER_ORIENTATIONS + co_index;
+                let state = CubeState::from_corner_state_index(index as u32);
+                targets
+                    .iter()
+                    .any(|cycle_type| state.induces_corner_cycle_type(cycle_type, &mut multi_bv))
+                    .then_some(class * CORNER_ORIENTATIONS + co_index)
+            })
+        }),
+        |index| corner_neighbours(&cp, &co, index),
+    )
+}
+
+/// Generates a pruning table for the orientation of the edges of a Rubik's
+/// Cube, whose goal states are the edge orientations that some state inducing
+/// the edge partition of a target has.
+pub fn generate_pruning_table_edges_o(
+    targets: impl Into<Targets>,
+) -> Result<PackedTable, TableError> {
+    Ok(generate_edges_o(&edges_o_header(targets), &Monitor::new())?.0)
+}
+
+fn generate_edges_o(
+    header: &TableHeader,
+    monitor: &Monitor,
+) -> Result<(PackedTable, TableStats), TableError> {
+    let targets = &header.targets;
+    generate_pruning_table(
+        header,
+        monitor,
+        (0..EDGE_ORIENTATIONS).into_par_iter().filter(|&index| {
+            let state = CubeState::from_edge_orientation_index(index as u16);
+            targets
+                .iter()
+                .any(|cycle_type| state.admits_edge_orientation(cycle_type))
+        }),
+        edge_orientation_neighbours(),
+    )
+}
+
+/// Generates a pruning table for the permutation of the edges of a Rubik's
+/// Cube, whose goal states are the edge permutations that some state inducing
+/// the edge partition of a target has.
+pub fn generate_pruning_table_edges_p(
+    targets: impl Into<Targets>,
+) -> Result<PackedTable, TableError> {
+    Ok(generate_edges_p(&edges_p_header(targets), &Monitor::new())?.0)
+}
+
+fn generate_edges_p(
+    header: &TableHeader,
+    monitor: &Monitor,
+) -> Result<(PackedTable, TableStats), TableError> {
+    let targets = &header.targets;
+    generate_pruning_table(
+        header,
+        monitor,
+        permutation_indices_admitting_any(
+            cube::EDGES,
+            targets.iter().map(|cycle_type| &cycle_type.edge_partition),
+        )
+        .into_par_iter()
+        .map(|index| index as usize),
+        edge_permutation_neighbours(&MoveTable::edge()),
+    )
+}
+
+/// Generates a pattern database over a subset of the edges of a Rubik's
+/// Cube, whose goal states are the placements of those edges that some state
+/// inducing the edge partition of a target has.
+pub fn generate_pruning_table_partial_edges(
+    targets: impl Into<Targets>,
+    edges: &[u8],
+) -> Result<PackedTable, TableError> {
+    Ok(generate_partial_edges(
+        &partial_edges_header(targets, edges),
+        &Monitor::new(),
+        edges,
+    )?
+    .0)
+}
+
+fn generate_partial_edges(
+    header: &TableHeader,
+    monitor: &Monitor,
+    edges: &[u8],
+) -> Result<(PackedTable, TableStats), TableError> {
+    let targets = &header.targets;
+    let k = edges.len();
+    let mut placements: Vec<usize> = permutation_indices_admitting_any(
+        cube::EDGES,
+        targets.iter().map(|cycle_type| &cycle_type.edge_partition),
+    )
+    .into_par_iter()
+    .map(|ep| CubeState::from_edge_permutation_index(ep).partial_edge_index(edges) >> k)
+    .collect();
+    placements.par_sort_unstable();
+    placements.dedup();
+    generate_pruning_table(
+        header,
+        monitor,
+        // only the orientations of placements that some admitted edge
+        // permutation has are tried
+        placements
+            .into_par_iter()
+            .flat_map_iter(|placement| (0..1 << k).map(move |flips| (placement << k) + flips))
+            .filter(|&index| {
+                let state = CubeState::from_partial_edges(edges, index);
+                targets
+                    .iter()
+                    .any(|cycle_type| state.admits_partial_edges(edges, cycle_type))
+            }),
+        partial_edge_neighbours(&MoveTable::edge(), edges),
+    )
+}
+
+fn generate_pattern(
+    header: &TableHeader,
+    monitor: &Monitor,
+    pattern: &Pattern,
+    target: Option<usize>,
+) -> Result<(PackedTable, TableStats), TableError> {
+    let is_goal = is_pattern_goal(pattern, target, &header.targets);
+    let goals: Vec<usize> = match target {
+        Some(target) => vec![target],
+        None => (0..header.len)
+            .into_par_iter()
+            .with_min_len(GENERATION_CHUNK)
+            .filter(|&index| is_goal(index))
+            .collect(),
+    };
+    generate_pruning_table(
+        header,
+        monitor,
+        goals.into_par_iter(),
+        pattern_neighbours(pattern),
+    )
+}
+
+/// A way in which a table disagrees with the cycle type it was generated
+/// for, as found by `verify_table`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Inconsistency {
+    /// The state is a goal state but its depth isn't 0, or the reverse.
+    Goal { index: usize, depth: u8 },
+    /// The state is not a goal state, but none of its neighbours is one
+    /// move closer to the goal states.
+    NoParent { index: usize, depth: u8 },
+    /// A neighbour of the state is more than one move closer to the goal
+    /// states.
+    Shortcut {
+        index: usize,
+        depth: u8,
+        neighbour: usize,
+        neighbour_depth: u8,
+    },
+    /// The state is unfilled, but one of its neighbours is reachable.
+    Unreached { index: usize, neighbour: usize },
+    /// Two sym-coordinates of conjugate states have different depths.
+    Asymmetric { index: usize, equivalent: usize },
+    /// The state is deeper than the bound of a depth-bounded table.
+    BeyondBound { index: usize, depth: u8 },
+}
+
+impl fmt::Display for Inconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Inconsistency::Goal { index, depth } => write!(
+                f,
+                "entry {} has depth {} but is {}a goal state",
+                index,
+                depth,
+                if depth == 0 { "not " } else { "" }
+            ),
+            Inconsistency::NoParent { index, depth } => write!(
+                f,
+                "entry {} has depth {} but no neighbour one move closer",
+                index, depth
+            ),
+            Inconsistency::Shortcut {
+                index,
+                depth,
+                neighbour,
+                neighbour_depth,
+            } => write!(
+                f,
+                "entry {} has depth {} but its neighbour {} has depth {}",
+                index, depth, neighbour, neighbour_depth
+            ),
+            Inconsistency::Unreached { index, neighbour } => write!(
+                f,
+                "entry {} is unfilled but its neighbour {} is filled",
+                index, neighbour
+            ),
+            Inconsistency::Asymmetric { index, equivalent } => write!(
+                f,
+                "entries {} and {} are for conjugate states but differ",
+                index, equivalent
+            ),
+            Inconsistency::BeyondBound { index, depth } => {
+                write!(f, "entry {} has depth {} beyond the bound", index, depth)
+            }
+        }
     }
-    let elapsed = now.elapsed();
-    println!("Pruning table for {} generated in {:.2?}.", tag, elapsed);
-    write_table(&table, tag.to_string() + ".pt");
-    table
+}
+
+/// Checks that a table holds the distances to the goal states of the table
+/// described by `header`, returning the inconsistency with the smallest
+/// index if it doesn't.
+///
+/// The goal states must be exactly the entries at depth 0: for corners, the
+/// states whose corners induce the corner partition according to
+/// `cube::induces_oriented_partition`, and for edges, the projections of the
+/// states inducing the edge partition. Every other entry at depth `d` must
+/// have a neighbour at depth `d - 1` and none below it, which, as the moves
+/// are closed under inverses, makes `d` the distance. The entries of
+/// unreachable states must be unfilled. In a depth-bounded table the entries
+/// at the bound `k + 1` need no neighbour at depth `k`, and none may be
+/// deeper.
+///
+/// A `Packing::Mod3` table only stores depths modulo 3, so for it goal
+/// states must be at residue 0 and every other entry must have a neighbour
+/// one move closer.
+pub fn verify_table(header: &TableHeader, table: &PackedTable) -> Result<(), Inconsistency> {
+    let targets = &header.targets;
+    match &header.coordinate {
+        Coordinate::CornerSymmetry => {
+            let symmetries = &*CORNER_SYMMETRIES;
+            let (cp, co) = (
+                MoveTable::corner_permutation(),
+                MoveTable::corner_orientation(),
+            );
+            check_distances(
+                table,
+                header.depth_bound,
+                |index| {
+                    let state = symmetries.representative(index);
+                    targets.iter().any(|cycle_type| {
+                        state.induces_corner_cycle_type(cycle_type, &mut [0; cube::CORNERS])
+                    })
+                },
+                |index| corner_neighbours(&cp, &co, index),
+                |index| symmetries.equivalents(index),
+            )
+        }
+        Coordinate::EdgeOrientation => check_distances(
+            table,
+            header.depth_bound,
+            |index| {
+                let state = CubeState::from_edge_orientation_index(index as u16);
+                targets
+                    .iter()
+                    .any(|cycle_type| state.admits_edge_orientation(cycle_type))
+            },
+            edge_orientation_neighbours(),
+            |_| None,
+        ),
+        Coordinate::EdgePermutation => check_distances(
+            table,
+            header.depth_bound,
+            |index| {
+                let state = CubeState::from_edge_permutation_index(index as u32);
+                targets.iter().any(|cycle_type| {
+                    state.admits_edge_permutation(cycle_type, &mut [0; cube::EDGES])
+                })
+            },
+            edge_permutation_neighbours(&MoveTable::edge()),
+            |_| None,
+        ),
+        Coordinate::PartialEdges(edges) => check_distances(
+            table,
+            header.depth_bound,
+            |index| {
+                let state = CubeState::from_partial_edges(edges, index);
+                targets
+                    .iter()
+                    .any(|cycle_type| state.admits_partial_edges(edges, cycle_type))
+            },
+            partial_edge_neighbours(&MoveTable::edge(), edges),
+            |_| None,
+        ),
+        Coordinate::Pattern { pattern, target } => check_distances(
+            table,
+            header.depth_bound,
+            is_pattern_goal(pattern, *target, targets),
+            pattern_neighbours(pattern),
+            |_| None,
+        ),
+    }
+}
+
+// Checks every entry of a table as described by `verify_table`, given its
+// depth bound, the goal states, the neighbours of each entry and the entries
+// that must have the same depth as it.
+fn check_distances<N: IntoIterator<Item = usize>, E: IntoIterator<Item = usize>>(
+    table: &PackedTable,
+    depth_bound: Option<u8>,
+    is_goal: impl Fn(usize) -> bool + Sync,
+    neighbours: impl Fn(usize) -> N + Sync,
+    equivalents: impl Fn(usize) -> E + Sync,
+) -> Result<(), Inconsistency> {
+    let unfilled = table.packing().unfilled();
+    let check = |index: usize| {
+        let depth = table.get(index);
+        if let Some(equivalent) = equivalents(index)
+            .into_iter()
+            .find(|&e| table.get(e) != depth)
+        {
+            return Err(Inconsistency::Asymmetric { index, equivalent });
+        }
+        if depth == unfilled {
+            return match neighbours(index)
+                .into_iter()
+                .find(|&next| table.get(next) != unfilled)
+            {
+                Some(neighbour) => Err(Inconsistency::Unreached { index, neighbour }),
+                None if is_goal(index) => Err(Inconsistency::Goal { index, depth }),
+                None => Ok(()),
+            };
+        }
+        let goal = is_goal(index);
+        match table.packing() {
+            Packing::Nibble if goal != (depth == 0) => Err(Inconsistency::Goal { index, depth }),
+            Packing::Mod3 if goal && depth != 0 => Err(Inconsistency::Goal { index, depth }),
+            _ if goal => Ok(()),
+            Packing::Nibble if depth_bound.is_some_and(|bound| depth > bound + 1) => {
+                Err(Inconsistency::BeyondBound { index, depth })
+            }
+            Packing::Nibble => {
+                let mut parent = depth_bound == Some(depth - 1);
+                for neighbour in neighbours(index) {
+                    let neighbour_depth = table.get(neighbour);
+                    if neighbour_depth < depth - 1 {
+                        return Err(Inconsistency::Shortcut {
+                            index,
+                            depth,
+                            neighbour,
+                            neighbour_depth,
+                        });
+                    }
+                    parent |= neighbour_depth == depth - 1;
+                }
+                if parent {
+                    Ok(())
+                } else {
+                    Err(Inconsistency::NoParent { index, depth })
+                }
+            }
+            Packing::Mod3 => {
+                if neighbours(index)
+                    .into_iter()
+                    .any(|next| table.get(next) == (depth + 2) % 3)
+                {
+                    Ok(())
+                } else {
+                    Err(Inconsistency::NoParent { index, depth })
+                }
+            }
+        }
+    };
+    match (0..table.len())
+        .into_par_iter()
+        .with_min_len(GENERATION_CHUNK)
+        .find_map_first(|index| check(index).err())
+    {
+        Some(inconsistency) => Err(inconsistency),
+        None => Ok(()),
+    }
+}
+
+/// Reads the header and statistics of the table file at `path`. Tables
+/// written before the statistics were stored with them are given the
+/// statistics of `table_stats`, for which a symmetry-reduced corner table is
+/// read in full.
+pub fn read_stats(
+    path: impl AsRef<Path>,
+) -> Result<(table_file::TableInfo, TableStats), TableError> {
+    let path = path.as_ref();
+    let refused = |source| TableError::Read {
+        path: path.to_path_buf(),
+        source,
+    };
+    let info = table_file::read_info(path).map_err(refused)?;
+    let stats = match (&info.stats, &info.header.coordinate) {
+        (Some(stats), _) => stats.clone(),
+        (None, Coordinate::CornerSymmetry) => {
+            let (_, table) = table_file::load(path, &info.header).map_err(refused)?;
+            table_stats(&info.header, &info.histogram, &table)
+        }
+        (None, _) => TableStats::from_histogram(&info.histogram),
+    };
+    Ok((info, stats))
+}
+
+/// Verifies the table file at `path`: its checksum and histogram, as
+/// `table_file::read_any` does, and then its contents, as `verify_table` does.
+/// Returns the header and histogram of the file.
+pub fn verify_file(path: impl AsRef<Path>) -> Result<table_file::TableInfo, TableError> {
+    let path = path.as_ref();
+    let refused = |source| TableError::Read {
+        path: path.to_path_buf(),
+        source,
+    };
+    let (info, table) = table_file::read_any(path).map_err(refused)?;
+    verify_table(&info.header, &table).map_err(|inconsistency| TableError::Inconsistent {
+        path: path.to_path_buf(),
+        inconsistency,
+    })?;
+    Ok(info)
+}
+
+// The tables for `C:1o,2n,3o` with each packing, shared by the tests of
+// every module. They are generated once, in a temporary directory that is
+// removed straight away, so tables added to them later are not kept.
+#[cfg(test)]
+pub(crate) fn test_tables(packing: Packing) -> std::sync::Arc<PruningTables> {
+    use std::sync::{Arc, OnceLock};
+    static NIBBLE: OnceLock<Arc<PruningTables>> = OnceLock::new();
+    static MOD3: OnceLock<Arc<PruningTables>> = OnceLock::new();
+    let tables = match packing {
+        Packing::Nibble => &NIBBLE,
+        Packing::Mod3 => &MOD3,
+    };
+    tables
+        .get_or_init(|| {
+            let dir = std::env::temp_dir().join(format!(
+                "rusty-rubik-test-tables-{:?}-{}",
+                packing,
+                std::process::id()
+            ));
+            let cycle_type: CycleType<u8> = "C:1o,2n,3o".parse().unwrap();
+            let tables =
+                PruningTables::load(&dir, &cycle_type, packing, TablePolicy::LoadOrGenerate)
+                    .unwrap();
+            std::fs::remove_dir_all(&dir).unwrap();
+            Arc::new(tables)
+        })
+        .clone()
 }
 
 #[cfg(test)]
@@ -141,14 +1783,513 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_correct_values() {
-        let pruning_tables = PruningTables::from(
-            "corners1o2n3o",
-            &CycleType {
-                corner_partition: vec![(1, true), (2, false), (3, true)],
-                ..Default::default()
+    fn test_edge_orientation_table() {
+        let cycle_type = CycleType {
+            edge_partition: vec![(2, true), (2, true)],
+            ..Default::default()
+        };
+        let table = generate_pruning_table_edges_o(&cycle_type).unwrap();
+        assert!(table.iter().all(|depth| depth != table::UNFILLED));
+
+        let solved = CubeState::default();
+        assert_eq!(table.get(solved.edge_orientation_index() as usize), 3);
+        let f = solved.apply_move_instance(&cube::ALL_MOVES[12]);
+        assert_eq!(table.get(f.edge_orientation_index() as usize), 2);
+        // every state is at most one move further than its neighbours
+        for index in 0..EDGE_ORIENTATIONS {
+            let state = CubeState::from_edge_orientation_index(index as u16);
+            let depth = table.get(state.edge_orientation_index() as usize);
+            assert_eq!(depth == 0, state.admits_edge_orientation(&cycle_type));
+            for m in cube::ALL_MOVES.iter() {
+                let next = state.apply_move_instance(m);
+                assert!(
+                    table
+                        .get(next.edge_orientation_index() as usize)
+                        .abs_diff(depth)
+                        <= 1
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_table() {
+        let cycle_type = CycleType {
+            edge_partition: vec![(2, true), (2, true)],
+            ..Default::default()
+        };
+        let header = edges_o_header(&cycle_type);
+        let mut table = generate_pruning_table_edges_o(&cycle_type).unwrap();
+        assert_eq!(verify_table(&header, &table), Ok(()));
+        let mod3 = TableHeader {
+            packing: Packing::Mod3,
+            ..header.clone()
+        };
+        assert_eq!(verify_table(&mod3, &table.to_mod3()), Ok(()));
+        let edges = [0, 1, 2];
+        assert_eq!(
+            verify_table(
+                &partial_edges_header(&cycle_type, &edges),
+                &generate_pruning_table_partial_edges(&cycle_type, &edges).unwrap()
+            ),
+            Ok(())
+        );
+
+        let solved = CubeState::default().edge_orientation_index() as usize;
+        table.set(solved, 2);
+        assert_eq!(
+            verify_table(&header, &table),
+            Err(Inconsistency::NoParent {
+                index: solved,
+                depth: 2
+            })
+        );
+        table.set(solved, 4);
+        assert!(matches!(
+            verify_table(&header, &table),
+            Err(Inconsistency::Shortcut { .. })
+        ));
+        let goal = (0..EDGE_ORIENTATIONS)
+            .find(|&index| table.get(index) == 0)
+            .unwrap();
+        table.set(solved, 3);
+        table.set(goal, 1);
+        assert_eq!(
+            verify_table(&header, &table),
+            Err(Inconsistency::Goal {
+                index: goal,
+                depth: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_move_table_neighbours() {
+        let edge = MoveTable::edge();
+        let edges = [3, 7, 0, 10];
+        let ep = edge_permutation_neighbours(&edge);
+        let partial = partial_edge_neighbours(&edge, &edges);
+        for index in (0..479_001_600).step_by(1_000_003) {
+            let state = CubeState::from_edge_permutation_index(index as u32);
+            let expected = neighbours_of(&state, |state| state.edge_permutation_index() as usize);
+            assert_eq!(ep(index), expected);
+        }
+        for index in (0..partial_edges_size(edges.len())).step_by(101) {
+            let state = CubeState::from_partial_edges(&edges, index);
+            let expected = neighbours_of(&state, |state| state.partial_edge_index(&edges));
+            assert_eq!(partial(index), expected);
+        }
+
+        let symmetries = &*CORNER_SYMMETRIES;
+        let (cp, co) = (
+            MoveTable::corner_permutation(),
+            MoveTable::corner_orientation(),
+        );
+        for index in (0..symmetries.len()).step_by(997) {
+            let state = symmetries.representative(index);
+            let mut expected: Vec<usize> = cube::ALL_MOVES
+                .iter()
+                .flat_map(|m| {
+                    let next = symmetries.sym_coordinate(&state.apply_move_instance(m));
+                    std::iter::once(next).chain(symmetries.equivalents(next))
+                })
+                .collect();
+            let mut neighbours: Vec<usize> = corner_neighbours(&cp, &co, index).collect();
+            expected.sort_unstable();
+            neighbours.sort_unstable();
+            assert_eq!(neighbours, expected);
+        }
+    }
+
+    // a plain breadth-first search from the goal states, one state at a time,
+    // turning whole states rather than looking moves up
+    fn serial_table(
+        len: usize,
+        is_goal: impl Fn(usize) -> bool,
+        neighbours: impl Fn(usize) -> [usize; MOVES],
+    ) -> PackedTable {
+        let mut table = PackedTable::new(len, Packing::Nibble);
+        let mut queue = std::collections::VecDeque::new();
+        for index in (0..len).filter(|&index| is_goal(index)) {
+            table.set(index, 0);
+            queue.push_back(index);
+        }
+        while let Some(index) = queue.pop_front() {
+            let depth = table.get(index);
+            for next in neighbours(index) {
+                if table.get(next) == table::UNFILLED {
+                    table.set(next, depth + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+        table
+    }
+
+    #[test]
+    fn test_parallel_matches_serial() {
+        let cycle_type: CycleType<u8> = "E:2o,2o".parse().unwrap();
+        let edges = [0, 1, 2];
+        let serial_eo = serial_table(
+            EDGE_ORIENTATIONS,
+            |index| {
+                CubeState::from_edge_orientation_index(index as u16)
+                    .admits_edge_orientation(&cycle_type)
+            },
+            |index| {
+                let state = CubeState::from_edge_orientation_index(index as u16);
+                neighbours_of(&state, |state| state.edge_orientation_index() as usize)
+            },
+        );
+        let serial_partial = serial_table(
+            partial_edges_size(edges.len()),
+            |index| {
+                CubeState::from_partial_edges(&edges, index)
+                    .admits_partial_edges(&edges, &cycle_type)
+            },
+            |index| {
+                let state = CubeState::from_partial_edges(&edges, index);
+                neighbours_of(&state, |state| state.partial_edge_index(&edges))
             },
         );
+        // several threads even on a single core, so that they interleave
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        for packing in [Packing::Nibble, Packing::Mod3] {
+            let pack = |table: &PackedTable| match packing {
+                Packing::Nibble => table.clone(),
+                Packing::Mod3 => table.to_mod3(),
+            };
+            let generate = |header: TableHeader| {
+                let header = TableHeader { packing, ..header };
+                pool.install(|| generate_table(&header, &Monitor::new()).unwrap().0)
+            };
+            let eo = generate(edges_o_header(&cycle_type));
+            assert_eq!(eo.as_bytes(), pack(&serial_eo).as_bytes());
+            let partial = generate(partial_edges_header(&cycle_type, &edges));
+            assert_eq!(partial.as_bytes(), pack(&serial_partial).as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_partial_edge_table() {
+        let cycle_type = CycleType {
+            edge_partition: vec![(2, true), (2, true)],
+            ..Default::default()
+        };
+        let edges = [0, 1, 2];
+        let table = generate_pruning_table_partial_edges(&cycle_type, &edges).unwrap();
+        assert_eq!(table.len(), 12 * 11 * 10 * 8);
+        assert!(table.iter().all(|depth| depth != table::UNFILLED));
+
+        for (index, depth) in table.iter().enumerate() {
+            let state = CubeState::from_partial_edges(&edges, index);
+            assert_eq!(depth == 0, state.admits_partial_edges(&edges, &cycle_type));
+            for m in cube::ALL_MOVES.iter() {
+                let next = state.apply_move_instance(m);
+                assert!(table.get(next.partial_edge_index(&edges)).abs_diff(depth) <= 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_targets() {
+        let targets: Targets = "E:2o,2o | E:4n".parse().unwrap();
+        let single: Vec<Targets> = targets.iter().map(Targets::from).collect();
+        assert!(edges_tag(&targets).starts_with("edges_any"));
+        assert_eq!(edges_tag(&single[0]), "edges2o2o");
+        // the corner partitions of both targets are solved corners
+        assert_eq!(corners_tag(&targets), "corners");
+        let with_corners: Targets = "C:1o,2n,3o | C:3o,2n,1o E:2o".parse().unwrap();
+        assert_eq!(corners_tag(&with_corners), "corners1o2n3o");
+        assert_eq!(with_corners.len(), 2);
+        let corners: Targets = "C:1o,7o | C:3o,5o".parse().unwrap();
+        assert_eq!(corners_tag(&corners), "corners_any5cc5000f");
+
+        // a state is as far from the union as from the nearest target
+        let union = generate_pruning_table_edges_o(&targets).unwrap();
+        assert_eq!(verify_table(&edges_o_header(&targets), &union), Ok(()));
+        let tables: Vec<PackedTable> = single
+            .iter()
+            .map(|targets| generate_pruning_table_edges_o(targets).unwrap())
+            .collect();
+        for (index, depth) in union.iter().enumerate() {
+            assert_eq!(depth, tables[0].get(index).min(tables[1].get(index)));
+        }
+        let edges = [0, 1, 2];
+        let union = generate_pruning_table_partial_edges(&targets, &edges).unwrap();
+        assert_eq!(
+            verify_table(&partial_edges_header(&targets, &edges), &union),
+            Ok(())
+        );
+        let tables: Vec<PackedTable> = single
+            .iter()
+            .map(|targets| generate_pruning_table_partial_edges(targets, &edges).unwrap())
+            .collect();
+        for (index, depth) in union.iter().enumerate() {
+            assert_eq!(depth, tables[0].get(index).min(tables[1].get(index)));
+        }
+    }
+
+    #[test]
+    fn test_reverse_lookup() {
+        let cycle_type: CycleType<u8> = "C:1o,2n,3o".parse().unwrap();
+        let tables = test_tables(Packing::Nibble);
+        let stats = tables.corner_stats();
+        assert_eq!(
+            tables.corner_count_at(..),
+            CORNER_STATES as u64 - stats.unreachable
+        );
+        assert_eq!(tables.corner_count_at(0..=0), stats.goals());
+
+        let goals: Vec<u32> = tables.corner_indices_at(0..=0).take(20).collect();
+        assert_eq!(goals.len(), 20);
+        assert!(goals.windows(2).all(|pair| pair[0] < pair[1]));
+        for (&index, state) in goals.iter().zip(tables.corner_states_at(0..=0)) {
+            assert_eq!(state.corner_state_index(), index);
+            assert!(state.induces_corner_cycle_type(&cycle_type, &mut [0; cube::CORNERS]));
+        }
+
+        let deepest = stats.max_depth().unwrap();
+        let mut rng = SplitMix64::new(7);
+        let sample: Vec<CubeState> = tables
+            .random_corner_states(deepest - 1.., &mut rng)
+            .take(20)
+            .collect();
+        assert_eq!(sample.len(), 20);
+        for state in sample.iter() {
+            assert!(tables.corner_depth(state) >= deepest - 1);
+        }
+        let again: Vec<CubeState> = tables
+            .random_corner_states(deepest - 1.., &mut SplitMix64::new(7))
+            .take(20)
+            .collect();
+        assert_eq!(sample, again);
+        assert_eq!(
+            tables.random_corner_states(deepest + 1.., &mut rng).next(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_depth_bound() {
+        let cycle_type = CycleType {
+            edge_partition: vec![(2, true), (2, true)],
+            ..Default::default()
+        };
+        let header = edges_o_header(&cycle_type).with_depth_bound(Some(2));
+        assert_eq!(table_tag(&header), "edges2o2o_o_d2");
+        let full = generate_pruning_table_edges_o(&cycle_type).unwrap();
+        let (mut bounded, stats) = generate_table(&header, &Monitor::new()).unwrap();
+        for index in 0..EDGE_ORIENTATIONS {
+            assert_eq!(bounded.get(index), full.get(index).min(3));
+        }
+        assert_eq!(stats, TableStats::from_histogram(&bounded.histogram()));
+        assert_eq!(stats.max_depth(), Some(3));
+        assert_eq!(verify_table(&header, &bounded), Ok(()));
+        let deep = (0..EDGE_ORIENTATIONS)
+            .find(|&index| full.get(index) > 3)
+            .unwrap();
+        bounded.set(deep, 4);
+        assert!(matches!(
+            verify_table(&header, &bounded),
+            Err(Inconsistency::BeyondBound { depth: 4, .. })
+        ));
+        assert!(matches!(
+            generate_table(
+                &header.with_depth_bound(Some(crate::table::MAX_DEPTH)),
+                &Monitor::new()
+            ),
+            Err(TableError::TooDeep(_))
+        ));
+    }
+
+    #[test]
+    fn test_monitor() {
+        use crate::progress::CancellationToken;
+        use std::sync::{Arc, Mutex};
+
+        let header = edges_o_header("E:2o,2o".parse::<Targets>().unwrap());
+        let depths = Arc::new(Mutex::new(vec![]));
+        let monitor = {
+            let depths = depths.clone();
+            Monitor::new().with_observer(move |event: &Event| {
+                if let Event::TableProgress {
+                    depth, filled, len, ..
+                } = *event
+                {
+                    depths.lock().unwrap().push((depth, filled, len));
+                }
+            })
+        };
+        let (table, _) = generate_table(&header, &monitor).unwrap();
+        let depths = depths.lock().unwrap();
+        let max_depth = table.histogram().iter().rposition(|&count| count > 0);
+        assert_eq!(depths.len(), max_depth.unwrap() + 1);
+        assert_eq!(depths.last(), Some(&(max_depth.unwrap() as u8, 2048, 2048)));
+
+        let token = CancellationToken::new();
+        token.cancel();
+        assert!(matches!(
+            generate_table(&header, &Monitor::new().with_cancellation(token)),
+            Err(TableError::Cancelled(tag)) if tag == "edges2o2o_o"
+        ));
+    }
+
+    #[test]
+    fn test_mod3_depths() {
+        let (nibble, mod3) = (test_tables(Packing::Nibble), test_tables(Packing::Mod3));
+        assert_eq!(mod3.packing(), Packing::Mod3);
+        // generated modulo 3 rather than converted
+        assert_eq!(mod3.stats()[0].0, "corners1o2n3o_mod3");
+        assert_eq!(mod3.corners, nibble.corners.to_mod3());
+        for index in [21458698, 54289714, 4817645, 665920, 23850765] {
+            assert_eq!(mod3.corners(index), nibble.corners(index));
+        }
+
+        let mut state = CubeState::default();
+        let mut h = mod3.h_value(&state);
+        for i in 0..40 {
+            state = state.apply_move_instance(&cube::ALL_MOVES[i * 7 % cube::ALL_MOVES.len()]);
+            h = mod3.h_value_from_parent(&state, h);
+            assert_eq!(h, nibble.h_value(&state));
+            assert_eq!(h, mod3.h_value(&state));
+        }
+
+        // unreachable states are told apart from depths in either packing
+        let dir = std::env::temp_dir().join(format!("rusty-rubik-mod3-{}", std::process::id()));
+        let impossible: CycleType<u8> = "C:1o".parse().unwrap();
+        for packing in [Packing::Nibble, Packing::Mod3] {
+            let tables =
+                PruningTables::load(&dir, &impossible, packing, TablePolicy::LoadOrGenerate)
+                    .unwrap();
+            let state = CubeState::default();
+            assert_eq!(tables.corner_depth(&state), table::UNFILLED);
+            assert_eq!(tables.corner_depth_from_parent(&state, 5), table::UNFILLED);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_table_policy() {
+        let cycle_type: CycleType<u8> = "C:1o,2n,3o".parse().unwrap();
+        let dir = std::env::temp_dir().join(format!("rusty-rubik-policy-{}", std::process::id()));
+        let load = |policy| PruningTables::load(&dir, &cycle_type, Packing::Nibble, policy);
+        assert!(matches!(
+            load(TablePolicy::LoadOnly),
+            Err(TableError::Missing(path)) if path == table_path(&dir, "corners1o2n3o")
+        ));
+
+        // a damaged table is reported rather than regenerated
+        load(TablePolicy::LoadOrGenerate).unwrap();
+        let path = table_path(&dir, "corners1o2n3o");
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+        for policy in [TablePolicy::LoadOnly, TablePolicy::LoadOrGenerate] {
+            assert!(matches!(
+                load(policy),
+                Err(TableError::Read {
+                    source: TableFileError::Truncated,
+                    ..
+                })
+            ));
+        }
+
+        std::fs::write(&path, &bytes).unwrap();
+        let tables = load(TablePolicy::LoadOnly).unwrap();
+        assert!(matches!(
+            tables.try_with_partial_edges(&[3, 3]),
+            Err(TableError::InvalidEdges(_))
+        ));
+
+        let pattern = Pattern::new().with_corners(&[0, 1, 2], true);
+        let goal = PatternGoal::Targets(cycle_type.clone().into());
+        assert!(matches!(
+            load(TablePolicy::LoadOnly)
+                .unwrap()
+                .try_with_pattern(pattern.clone(), &goal),
+            Err(TableError::Missing(_))
+        ));
+        let tables = load(TablePolicy::LoadOrGenerate)
+            .unwrap()
+            .try_with_pattern(pattern.clone(), &goal)
+            .unwrap();
+        assert_eq!(tables.stats()[1].0, "pattern_c0-1-2o_corners1o2n3o");
+        let patterns = tables.patterns();
+        assert_eq!(
+            verify_table(&pattern_header(&pattern, &goal), patterns[0].table()),
+            Ok(())
+        );
+        // the bounds are combined by max
+        let state = CubeState::from_pattern_index(&pattern, 1234);
+        assert_eq!(
+            tables.compute_h_value(&state),
+            patterns[0]
+                .compute_h_value(&state)
+                .max(tables.corners(state.corner_state_index() as usize))
+        );
+        assert!(matches!(
+            tables.try_with_pattern(Pattern::new(), &goal),
+            Err(TableError::InvalidPattern(_))
+        ));
+
+        // a table in the raw format is only migrated when writing is allowed
+        // copied out of any memory map before the file is overwritten
+        let corners = load(TablePolicy::LoadOnly).unwrap().corners.clone();
+        let raw = corners.as_bytes().to_vec();
+        std::fs::write(&path, &raw).unwrap();
+        assert_eq!(load(TablePolicy::LoadOnly).unwrap().corners, corners);
+        assert_eq!(std::fs::read(&path).unwrap(), raw);
+        assert_eq!(load(TablePolicy::LoadOrGenerate).unwrap().corners, corners);
+        assert!(table_file::has_magic(&std::fs::read(&path).unwrap()));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_stats() {
+        let cycle_type = CycleType {
+            corner_partition: vec![(1, true), (2, false), (3, true)],
+            ..Default::default()
+        };
+        let tables = test_tables(Packing::Nibble);
+        let (tag, stats) = &tables.stats()[0];
+        assert_eq!(tag, "corners1o2n3o");
+        // the entries stand for every corner state
+        assert_eq!(stats.states(), CORNER_STATES as u64);
+        // 8!/(3 * 2 * 3!) placements of the cycles; 3 choices of the fixed
+        // corner to twist, 18 twists of it and the 3-cycle summing to 0, and
+        // 3 ways to leave the 2-cycle untwisted
+        assert_eq!(stats.goals(), 1120 * 3 * 18 * 3);
+        assert_eq!(stats.max_depth(), Some(6));
+        // as counted during generation, and by weighting each entry
+        assert_eq!(
+            stats,
+            &table_stats(&corners_header(&cycle_type), &[], &tables.corners)
+        );
+        assert_eq!(test_tables(Packing::Mod3).corner_stats(), stats);
+
+        let cycle_type = CycleType {
+            edge_partition: vec![(2, true), (2, true)],
+            ..Default::default()
+        };
+        let table = generate_pruning_table_edges_o(&cycle_type).unwrap();
+        let header = edges_o_header(&cycle_type);
+        let stats = table_stats(&header, &table.histogram(), &table);
+        assert_eq!(stats.states(), EDGE_ORIENTATIONS as u64);
+        assert_eq!(
+            stats.histogram[3],
+            table.iter().filter(|&d| d == 3).count() as u64
+        );
+    }
+
+    #[test]
+    fn test_correct_values() {
+        let pruning_tables = PruningTables::from(&CycleType {
+            corner_partition: vec![(1, true), (2, false), (3, true)],
+            ..Default::default()
+        });
         assert_eq!(pruning_tables.corners(21458698), 3);
         assert_eq!(pruning_tables.corners(54289714), 4);
         assert_eq!(pruning_tables.corners(4817645), 2);
@@ -0,0 +1,69 @@
+//! A small seeded pseudorandom number generator, so that random samples of
+//! states can be reproduced without depending on a random number crate.
+
+/// The SplitMix64 generator of Steele, Lea and Flood, whose whole state is
+/// one `u64`. It is fast and statistically sound, but not cryptographically
+/// secure.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    /// The generator seeded with `seed`. Generators with the same seed
+    /// produce the same numbers.
+    pub fn new(seed: u64) -> Self {
+        SplitMix64 { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number drawn uniformly from `0..n`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is 0.
+    pub fn below(&mut self, n: u64) -> u64 {
+        assert!(n > 0, "cannot draw a number below 0");
+        // the high half of a 128-bit product, rejecting the low products
+        // that would make some results more likely than others
+        let threshold = n.wrapping_neg() % n;
+        loop {
+            let product = self.next_u64() as u128 * n as u128;
+            if product as u64 >= threshold {
+                return (product >> 64) as u64;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_mix_64() {
+        // the reference outputs for the seed 0
+        let mut rng = SplitMix64::new(0);
+        assert_eq!(rng.next_u64(), 0xe220a8397b1dcdaf);
+        assert_eq!(rng.next_u64(), 0x6e789e6aa1b965f4);
+        assert_eq!(rng.next_u64(), 0x06c45d188009454f);
+
+        let mut rng = SplitMix64::new(42);
+        let mut seen = [false; 7];
+        for _ in 0..1000 {
+            seen[rng.below(7) as usize] = true;
+        }
+        assert!(seen.iter().all(|&seen| seen));
+        assert_eq!(
+            SplitMix64::new(42).below(1 << 40),
+            SplitMix64::new(42).below(1 << 40)
+        );
+    }
+}
@@ -1,56 +1,110 @@
 //! Contains utility methods of various puzzle solving methods.
 //!
-//! Includes iterative deepening A* (IDA*).
+//! Includes iterative deepening A* (IDA*), and a walk down the corner pruning
+//! table for targets that only constrain the corners.
 
 use crate::cube::{CubeState, MoveSequence};
+use crate::heuristic::Heuristic;
+use crate::progress::{Cancelled, Event, Monitor};
 use crate::pruning::PruningTables;
-use crate::{cube, CycleType};
+use crate::{cube, table, Targets};
+use std::time::Instant;
+
+// How many nodes are expanded between checks for cancellation, and between
+// progress reports within a depth.
+const CHECK_INTERVAL: u64 = 1 << 12;
+const REPORT_INTERVAL: u64 = 1 << 24;
 
 /**
  * A solver implementing the iterative deepening A* search algorithm [Korf, 1997].
  *
- * This solver uses the pruning tables pre-computed in `pruning.rs`
+ * This solver uses a `Heuristic`, usually the pruning tables pre-computed in `pruning.rs`,
  * to prevent the solver from exploring move sequences that will yield suboptimal
  * solutions. This is the method typically implemented in most optimal Rubik's Cube solvers.
  */
-pub struct IDASolver<'a> {
+pub struct IDASolver<'a, H: Heuristic + ?Sized = PruningTables> {
     start_state: CubeState,
-    pruning_tables: &'a PruningTables,
-    target_cycle_type: CycleType<u8>,
+    heuristic: &'a H,
+    targets: Targets,
     multi_bv: Vec<u8>,
+    monitor: Monitor,
+    // the number of states expanded so far, and when the search started
+    nodes: u64,
+    started: Instant,
 }
 
 enum SearchResult {
     Found,
     NewBound(u8),
+    Cancelled,
 }
 
-impl<'a> IDASolver<'a> {
-    pub fn new(
-        start_state: CubeState,
-        pruning_tables: &'a PruningTables,
-        target_cycle_type: CycleType<u8>,
-    ) -> Self {
+impl<'a, H: Heuristic + ?Sized> IDASolver<'a, H> {
+    /// A solver searching from `start_state` for the shortest move sequence
+    /// inducing any of `targets`, such as a single `CycleType`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `targets` is empty, since the search would never end.
+    pub fn new(start_state: CubeState, heuristic: &'a H, targets: impl Into<Targets>) -> Self {
+        let targets = targets.into();
+        assert!(!targets.is_empty(), "no targets to search for");
         Self {
             start_state,
-            pruning_tables,
-            target_cycle_type,
+            heuristic,
+            targets,
             multi_bv: vec![0; std::cmp::max(cube::EDGES, cube::CORNERS)],
+            monitor: Monitor::new(),
+            nodes: 0,
+            started: Instant::now(),
         }
     }
 
+    /// Reports the progress of the search to `monitor`, which can also
+    /// cancel it.
+    pub fn with_monitor(mut self, monitor: Monitor) -> Self {
+        self.monitor = monitor;
+        self
+    }
+
+    /// The number of states expanded by the searches so far.
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    fn report(&self, depth: u8) {
+        self.monitor.report(&Event::SearchProgress {
+            depth,
+            nodes: self.nodes,
+            elapsed: self.started.elapsed(),
+        });
+    }
+
     fn search_for_solution(
         &mut self,
         curr_path: &mut MoveSequence,
         last_state: &CubeState,
+        last_h: H::Node,
         g: u8,
         bound: u8,
     ) -> SearchResult {
-        let last_h = self.pruning_tables.compute_h_value(last_state);
-        let f = g + last_h;
+        let f = g.saturating_add(self.heuristic.bound(last_h));
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            if self.monitor.is_cancelled() {
+                return SearchResult::Cancelled;
+            }
+            if self.nodes.is_multiple_of(REPORT_INTERVAL) {
+                self.report(bound);
+            }
+        }
         if f > bound {
             SearchResult::NewBound(f)
-        } else if last_state.induces_cycle_type(&self.target_cycle_type, self.multi_bv.as_mut()) {
+        } else if self
+            .targets
+            .iter()
+            .any(|cycle_type| last_state.induces_cycle_type(cycle_type, &mut self.multi_bv))
+        {
             // yay it's solved!
             SearchResult::Found
         } else {
@@ -68,9 +122,13 @@ impl<'a> IDASolver<'a> {
                 }
                 curr_path.push(*m);
                 let next_state = last_state.apply_move_instance(m);
-                let t = self.search_for_solution(curr_path, &next_state, g + 1, bound);
+                // pruning tables may only store depths modulo 3, so the
+                // exact depths of the next state come from those of this one
+                let next_h = self.heuristic.evaluate_child(&next_state, last_h);
+                let t = self.search_for_solution(curr_path, &next_state, next_h, g + 1, bound);
                 match t {
                     SearchResult::Found => return SearchResult::Found,
+                    SearchResult::Cancelled => return SearchResult::Cancelled,
                     SearchResult::NewBound(b) => {
                         min = std::cmp::min(b, min);
                     }
@@ -81,24 +139,287 @@ impl<'a> IDASolver<'a> {
         }
     }
 
+    /// Finds an optimal solution.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the search is cancelled; see `try_solve`.
     // TODO: all solutions
     pub fn solve(&mut self) -> MoveSequence {
+        self.try_solve()
+            .unwrap_or_else(|err| panic!("search {}", err))
+    }
+
+    /// Finds an optimal solution, or fails with `Cancelled` soon after the
+    /// monitor of the solver is cancelled.
+    pub fn try_solve(&mut self) -> Result<MoveSequence, Cancelled> {
         let start_state = self.start_state.clone();
+        self.started = Instant::now();
 
         // initial lower bound on number of moves needed to solve start state
-        let mut bound = self.pruning_tables.compute_h_value(&start_state);
+        let start_h = self.heuristic.evaluate(&start_state);
+        let mut bound = self.heuristic.bound(start_h);
         let mut path: MoveSequence = MoveSequence::default();
         loop {
-            println!("Searching depth {}...", bound);
-            match self.search_for_solution(&mut path, &start_state, 0, bound) {
+            self.report(bound);
+            match self.search_for_solution(&mut path, &start_state, start_h, 0, bound) {
                 SearchResult::Found => {
                     break;
                 }
                 SearchResult::NewBound(t) => {
                     bound = t;
                 }
+                SearchResult::Cancelled => return Err(Cancelled),
             }
         }
-        path
+        Ok(path)
+    }
+}
+
+/**
+ * A solver reading optimal solutions off the corner pruning table, for
+ * callers that only care about the corners.
+ *
+ * The corner table stores the exact number of moves needed to bring the
+ * corners of each state into a goal, so from any state some move leads to a
+ * state one move closer. Following such moves reaches a goal in `d` steps of
+ * at most 18 lookups each, where an `IDASolver` would search the whole tree
+ * up to depth `d`.
+ *
+ * Elsewhere an empty edge partition means that the edges are solved, as
+ * `CubeState::induces_cycle_type` checks. A walk instead leaves the edges
+ * wherever its moves take them, so its solutions only satisfy
+ * `CubeState::induces_corner_cycle_type`, and it must be asked for with
+ * `ignoring_edges`.
+ */
+pub struct TableWalkSolver<'a> {
+    start_state: CubeState,
+    tables: &'a PruningTables,
+}
+
+impl<'a> TableWalkSolver<'a> {
+    /// A solver walking from `start_state` down the corner table of
+    /// `tables`, bringing the corners into a goal whatever becomes of the
+    /// edges. Returns `None` if some target of the tables moves an edge,
+    /// which the corner table knows nothing about.
+    pub fn ignoring_edges(start_state: CubeState, tables: &'a PruningTables) -> Option<Self> {
+        (!tables.targets().has_edges()).then_some(Self {
+            start_state,
+            tables,
+        })
+    }
+
+    /// The number of moves of an optimal solution, or `None` if it is beyond
+    /// the depth bound of depth-bounded tables, or if no goal can be reached.
+    pub fn depth(&self) -> Option<u8> {
+        let depth = self.tables.corner_depth(&self.start_state);
+        let bound = self.tables.depth_bound().unwrap_or(table::MAX_DEPTH);
+        (depth != table::UNFILLED && depth <= bound).then_some(depth)
+    }
+
+    /// Finds an optimal solution by taking the first move closer to a goal
+    /// from each state, or returns `None` as `depth` does.
+    pub fn solve(&self) -> Option<MoveSequence> {
+        let mut depth = self.depth()?;
+        let mut state = self.start_state.clone();
+        let mut solution = vec![];
+        while depth > 0 {
+            let (m, next) = cube::ALL_MOVES
+                .iter()
+                .map(|m| (*m, state.apply_move_instance(m)))
+                .find(|(_, next)| self.tables.corner_depth_from_parent(next, depth) + 1 == depth)
+                .expect("a state off the goal has a neighbour closer to it");
+            solution.push(m);
+            state = next;
+            depth -= 1;
+        }
+        Some(MoveSequence::from(solution))
+    }
+
+    /// Enumerates every optimal solution, branching on each move closer to a
+    /// goal. There are none if `depth` is `None`.
+    pub fn solutions(&self) -> Solutions<'a> {
+        let stack = match self.depth() {
+            Some(depth) => vec![(self.start_state.clone(), depth, 0)],
+            None => vec![],
+        };
+        Solutions {
+            tables: self.tables,
+            stack,
+            path: vec![],
+        }
+    }
+}
+
+/// The optimal solutions found by `TableWalkSolver::solutions`, in the order
+/// of `cube::ALL_MOVES`.
+pub struct Solutions<'a> {
+    tables: &'a PruningTables,
+    // the states along the current path, each with its depth and the index
+    // in `cube::ALL_MOVES` of the next move to try from it
+    stack: Vec<(CubeState, u8, usize)>,
+    path: Vec<cube::MoveInstance>,
+}
+
+impl Iterator for Solutions<'_> {
+    type Item = MoveSequence;
+
+    fn next(&mut self) -> Option<MoveSequence> {
+        while let Some((state, depth, next)) = self.stack.last() {
+            let depth = *depth;
+            if depth == 0 {
+                let solution = MoveSequence::from(self.path.clone());
+                self.stack.pop();
+                self.path.pop();
+                return Some(solution);
+            }
+            let closer = cube::ALL_MOVES[*next..]
+                .iter()
+                .map(|m| state.apply_move_instance(m))
+                .enumerate()
+                .map(|(i, child)| (*next + i, child))
+                .find(|(_, child)| self.tables.corner_depth_from_parent(child, depth) + 1 == depth);
+            match closer {
+                Some((i, child)) => {
+                    self.stack.last_mut().unwrap().2 = i + 1;
+                    self.path.push(cube::ALL_MOVES[i]);
+                    self.stack.push((child, depth - 1, 0));
+                }
+                None => {
+                    self.stack.pop();
+                    self.path.pop();
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table::Packing;
+    use crate::{parser, pruning};
+
+    #[test]
+    fn test_table_walk() {
+        let cycle_type: crate::CycleType<u8> = "C:1o,2n,3o".parse().unwrap();
+        let tables = pruning::test_tables(Packing::Nibble);
+        // the walk promises the corner cycle type and nothing about the edges
+        let goal = |state: &CubeState| state.induces_corner_cycle_type(&cycle_type, &mut [0; 8]);
+        for scramble in ["", "R", "R U F'", "R U2 D' B L2 F"] {
+            let state = parser::parse_scramble(scramble)
+                .unwrap()
+                .iter()
+                .fold(CubeState::default(), |state, m| {
+                    state.apply_move_instance(m)
+                });
+            let walk = TableWalkSolver::ignoring_edges(state.clone(), &tables).unwrap();
+            let depth = walk.depth().unwrap();
+            assert_eq!(depth, tables.corner_depth(&state));
+            let solution = walk.solve().unwrap();
+            assert_eq!(solution.len(), depth as usize);
+            assert!(goal(&state.apply_move_instances(&solution)));
+
+            let solutions: Vec<MoveSequence> = walk.solutions().collect();
+            assert!(!solutions.is_empty());
+            assert_eq!(solutions[0].to_string(), solution.to_string());
+            for solution in solutions.iter() {
+                assert_eq!(solution.len(), depth as usize);
+                assert!(goal(&state.apply_move_instances(solution)));
+            }
+            let mut distinct: Vec<String> = solutions.iter().map(|s| s.to_string()).collect();
+            distinct.sort();
+            distinct.dedup();
+            assert_eq!(distinct.len(), solutions.len());
+        }
+
+        // a walk down a table stored modulo 3 takes the same moves
+        let mod3 = pruning::test_tables(Packing::Mod3);
+        let state = CubeState::default().apply_move_instances(&MoveSequence::from(
+            parser::parse_scramble("R U2 D' B L2 F").unwrap(),
+        ));
+        let exact: Vec<String> = TableWalkSolver::ignoring_edges(state.clone(), &tables)
+            .unwrap()
+            .solutions()
+            .map(|solution| solution.to_string())
+            .collect();
+        let walked: Vec<String> = TableWalkSolver::ignoring_edges(state, &mod3)
+            .unwrap()
+            .solutions()
+            .map(|solution| solution.to_string())
+            .collect();
+        assert_eq!(exact, walked);
+
+        // every optimal solution of a short walk, found by brute force
+        let solved = CubeState::default();
+        let goal_state = solved.apply_move_instances(
+            &TableWalkSolver::ignoring_edges(solved.clone(), &tables)
+                .unwrap()
+                .solve()
+                .unwrap(),
+        );
+        let state = goal_state.apply_move_instances(&MoveSequence::from(
+            parser::parse_scramble("R U2 F").unwrap(),
+        ));
+        let walk = TableWalkSolver::ignoring_edges(state.clone(), &tables).unwrap();
+        let depth = walk.depth().unwrap();
+        assert!(depth <= 3);
+        let mut frontier = vec![state];
+        for _ in 0..depth {
+            frontier = frontier
+                .iter()
+                .flat_map(|state| cube::ALL_MOVES.iter().map(|m| state.apply_move_instance(m)))
+                .collect();
+        }
+        let expected = frontier.iter().filter(|state| goal(state)).count();
+        assert_eq!(walk.solutions().count(), expected);
+    }
+
+    #[test]
+    fn test_table_walk_unreachable() {
+        // a lone twisted corner is impossible, so no entry is filled
+        let dir = std::env::temp_dir().join(format!("rusty-rubik-walk-{}", std::process::id()));
+        let impossible: crate::CycleType<u8> = "C:1o".parse().unwrap();
+        for packing in [Packing::Nibble, Packing::Mod3] {
+            let tables = PruningTables::load(
+                &dir,
+                &impossible,
+                packing,
+                pruning::TablePolicy::LoadOrGenerate,
+            )
+            .unwrap();
+            let walk = TableWalkSolver::ignoring_edges(CubeState::default(), &tables).unwrap();
+            assert_eq!(walk.depth(), None);
+            assert!(walk.solve().is_none());
+            assert_eq!(walk.solutions().count(), 0);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "no targets")]
+    fn test_no_targets() {
+        IDASolver::new(
+            CubeState::default(),
+            &crate::heuristic::Zero,
+            Targets::default(),
+        );
+    }
+
+    #[test]
+    fn test_large_bounds() {
+        // a half turn of U, with every state other than it and the solved
+        // state bounded as far away as possible
+        let half: crate::CycleType<u8> = "C:2n,2n E:2n,2n".parse().unwrap();
+        let solved = CubeState::default();
+        let goal = solved.apply_move_instance(&cube::ALL_MOVES[2]);
+        let heuristic = crate::heuristic::from_fn(|state: &CubeState| match state {
+            state if *state == solved => 1,
+            state if *state == goal => 0,
+            _ => u8::MAX,
+        });
+        let solution = IDASolver::new(solved.clone(), &heuristic, half).solve();
+        assert_eq!(solution.len(), 1);
     }
 }
@@ -0,0 +1,347 @@
+//! A directory of pruning table files, shared by every solver in a process.
+//!
+//! Generating the tables for a cycle type can take minutes, so they are kept
+//! in one place regardless of the working directory: the directory given to
+//! `TableStore::new`, or else the one named by `TABLE_DIR_VAR`, or else
+//! `rusty-rubik` in the user's cache directory (`$XDG_CACHE_HOME`, falling
+//! back to `~/.cache` on Linux).
+//!
+//! A `TableStore` loads the tables for each set of `Targets` at most once
+//! and hands them out behind an `Arc`, so that any number of `IDASolver`s
+//! can share them.
+
+use crate::progress::Monitor;
+use crate::pruning::{PruningTables, TableError, TablePolicy};
+use crate::table::Packing;
+use crate::table_file::{self, TableFileError, TableInfo};
+use crate::Targets;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// The environment variable naming the table directory.
+pub const TABLE_DIR_VAR: &str = "RUSTY_RUBIK_TABLE_DIR";
+
+/// How long a partly written table file must have gone unmodified before
+/// `TableStore::prune` deletes it even though its writer may be running.
+pub const STALE_PARTIAL: Duration = Duration::from_secs(60 * 60);
+
+// the tables of some targets, with the edge subsets of their partial edge
+// pattern databases
+type TablesKey = (Targets, Vec<Vec<u8>>);
+// filled by the first caller to ask for the tables, while any others asking
+// for them wait for that load; it stays empty if the load fails
+type TablesSlot = Arc<Mutex<Option<Arc<PruningTables>>>>;
+
+/// A directory of pruning tables and the tables loaded from it so far.
+pub struct TableStore {
+    dir: PathBuf,
+    packing: Packing,
+    policy: TablePolicy,
+    depth_bound: Option<u8>,
+    monitor: Monitor,
+    loaded: Mutex<HashMap<TablesKey, TablesSlot>>,
+}
+
+/// A file in the table directory, as returned by `TableStore::list`.
+#[derive(Debug)]
+pub struct StoredTable {
+    pub path: PathBuf,
+    /// The size of the file in bytes.
+    pub size: u64,
+    /// When the file was last modified, if the platform records it.
+    pub modified: Option<SystemTime>,
+    /// The header and histogram of the file, or why it was refused.
+    pub info: Result<TableInfo, TableFileError>,
+}
+
+impl StoredTable {
+    /// Whether the file is a readable table, as opposed to a corrupted,
+    /// outdated or partly written one.
+    pub fn is_valid(&self) -> bool {
+        self.info.is_ok()
+    }
+
+    /// Whether the file is one that `table_file::write` writes and then
+    /// renames over a table, named `<table>.<pid>-<n>.partial`.
+    pub fn is_partial(&self) -> bool {
+        self.path
+            .extension()
+            .is_some_and(|extension| extension == "partial")
+    }
+
+    /// Whether the file is partly written and will never be renamed over a
+    /// table, because the process writing it is no longer running or it
+    /// hasn't been modified for `STALE_PARTIAL`.
+    pub fn is_abandoned(&self) -> bool {
+        let stale = self
+            .modified
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age >= STALE_PARTIAL);
+        self.is_partial() && (stale || !writer_running(&self.path))
+    }
+}
+
+// Whether the process named by a partly written file may still be writing
+// it. Without `/proc` to look the process up, it is assumed to be.
+fn writer_running(path: &Path) -> bool {
+    let pid = path
+        .file_stem()
+        .and_then(|stem| stem.to_str()?.rsplit('.').next()?.split('-').next())
+        .and_then(|pid| pid.parse::<u32>().ok());
+    let proc = Path::new("/proc");
+    match pid {
+        Some(pid) => !proc.is_dir() || proc.join(pid.to_string()).exists(),
+        None => false,
+    }
+}
+
+impl fmt::Display for StoredTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.path.file_name().unwrap_or_default().to_string_lossy();
+        write!(
+            f,
+            "{} ({:.1} MiB): ",
+            name,
+            self.size as f64 / (1 << 20) as f64
+        )?;
+        match &self.info {
+            _ if self.is_partial() => write!(f, "partly written"),
+            Ok(info) => write!(f, "{}", info.header),
+            Err(err) => write!(f, "invalid, {}", err),
+        }
+    }
+}
+
+impl TableStore {
+    /// A store keeping its tables in `dir`, which is created when the first
+    /// table is written.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        TableStore {
+            dir: dir.into(),
+            packing: Packing::Nibble,
+            policy: TablePolicy::LoadOrGenerate,
+            depth_bound: None,
+            monitor: Monitor::new(),
+            loaded: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// A store keeping its tables in `default_dir()`.
+    pub fn from_env() -> Self {
+        Self::new(Self::default_dir())
+    }
+
+    /// The directory named by `TABLE_DIR_VAR` if it is set, and otherwise
+    /// `rusty-rubik` in the user's cache directory. If there is no cache
+    /// directory either, this is the current directory.
+    pub fn default_dir() -> PathBuf {
+        match std::env::var_os(TABLE_DIR_VAR) {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => dirs::cache_dir()
+                .map(|cache| cache.join("rusty-rubik"))
+                .unwrap_or_else(|| PathBuf::from(".")),
+        }
+    }
+
+    /// Keeps the tables handed out by this store in memory with the given
+    /// packing, as in `PruningTables::from_with_packing`.
+    pub fn with_packing(mut self, packing: Packing) -> Self {
+        self.packing = packing;
+        self
+    }
+
+    /// Loads tables with the given policy instead of
+    /// `TablePolicy::LoadOrGenerate`.
+    pub fn with_policy(mut self, policy: TablePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Hands out depth-bounded tables, whose generation stops at the given
+    /// depth, as in `PruningTables::load_bounded`.
+    pub fn with_depth_bound(mut self, depth_bound: u8) -> Self {
+        self.depth_bound = Some(depth_bound);
+        self
+    }
+
+    /// Reports the progress of generating tables to `monitor`, which can
+    /// also cancel it.
+    pub fn with_monitor(mut self, monitor: Monitor) -> Self {
+        self.monitor = monitor;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the pruning tables for a cycle type or any set of `Targets`,
+    /// reading them from the table directory or generating them as the
+    /// policy says on the first successful call for equal targets.
+    pub fn tables(&self, targets: impl Into<Targets>) -> Result<Arc<PruningTables>, TableError> {
+        self.tables_with_partial_edges(targets, &[])
+    }
+
+    /// Like `tables`, but with a partial edge pattern database over each of
+    /// the given subsets of the edges, as added by
+    /// `PruningTables::try_with_partial_edges`.
+    pub fn tables_with_partial_edges(
+        &self,
+        targets: impl Into<Targets>,
+        edge_subsets: &[&[u8]],
+    ) -> Result<Arc<PruningTables>, TableError> {
+        let targets = targets.into();
+        let subsets = edge_subsets
+            .iter()
+            .map(|edges| {
+                let mut edges = edges.to_vec();
+                edges.sort_unstable();
+                edges
+            })
+            .collect();
+        let slot = self
+            .loaded
+            .lock()
+            .unwrap()
+            .entry((targets.clone(), subsets))
+            .or_default()
+            .clone();
+        let mut slot = slot.lock().unwrap();
+        if let Some(tables) = slot.as_ref() {
+            return Ok(tables.clone());
+        }
+        let tables = edge_subsets.iter().try_fold(
+            PruningTables::load_monitored(
+                &self.dir,
+                &targets,
+                self.packing,
+                self.policy,
+                self.depth_bound,
+                self.monitor.clone(),
+            )?,
+            |tables, edges| tables.try_with_partial_edges(edges),
+        )?;
+        Ok(slot.insert(Arc::new(tables)).clone())
+    }
+
+    /// Lists the table files in the table directory, along with any left
+    /// behind by an interrupted write, sorted by path. A missing directory
+    /// has no tables.
+    pub fn list(&self) -> std::io::Result<Vec<StoredTable>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(err),
+        };
+        let mut tables = vec![];
+        for entry in entries {
+            let entry = entry?;
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if !entry.file_type()?.is_file()
+                || !(name.ends_with(".pt") || name.ends_with(".partial"))
+            {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            tables.push(StoredTable {
+                size: metadata.len(),
+                modified: metadata.modified().ok(),
+                info: table_file::read_info(&path),
+                path,
+            });
+        }
+        tables.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(tables)
+    }
+
+    /// Deletes the invalid files in the table directory, and the valid ones
+    /// for which `keep` returns false, returning the paths deleted. Partly
+    /// written files are only deleted once they are abandoned, as told by
+    /// `StoredTable::is_abandoned`, so that writes in progress can finish.
+    ///
+    /// Tables already handed out are unaffected, but will be regenerated
+    /// when next asked for by another store or process.
+    pub fn prune(&self, keep: impl Fn(&StoredTable) -> bool) -> std::io::Result<Vec<PathBuf>> {
+        let mut removed = vec![];
+        for table in self.list()? {
+            let remove = if table.is_partial() {
+                table.is_abandoned()
+            } else {
+                !table.is_valid() || !keep(&table)
+            };
+            if remove {
+                std::fs::remove_file(&table.path)?;
+                removed.push(table.path);
+            }
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CycleType;
+
+    #[test]
+    fn test_store() {
+        let dir = std::env::temp_dir().join(format!("rusty-rubik-store-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cycle_type: CycleType<u8> = "C:3o,2n,1o".parse().unwrap();
+        TableStore::new(&dir).tables(&cycle_type).unwrap();
+        std::fs::write(dir.join("junk.pt"), b"not a table").unwrap();
+        std::fs::write(dir.join("notes.txt"), b"not a table either").unwrap();
+
+        let store = TableStore::new(&dir).with_policy(TablePolicy::LoadOnly);
+        let tables = store.tables(&cycle_type).unwrap();
+        assert_eq!(tables.dir(), dir);
+        assert_eq!(tables.targets(), &Targets::from(&cycle_type));
+        // the same tables are handed out for every form of the cycle type
+        let again = store
+            .tables("C:1o,2n,3o".parse::<CycleType<u8>>().unwrap())
+            .unwrap();
+        assert!(Arc::ptr_eq(&tables, &again));
+        let again = store
+            .tables("C:1o,2n,3o | C:3o,1o,2n".parse::<Targets>().unwrap())
+            .unwrap();
+        assert!(Arc::ptr_eq(&tables, &again));
+
+        let listed = store.list().unwrap();
+        assert_eq!(listed.len(), 2);
+        assert!(listed[0].is_valid());
+        assert!(!listed[1].is_valid());
+        assert_eq!(store.prune(|_| true).unwrap(), vec![dir.join("junk.pt")]);
+        assert_eq!(store.list().unwrap().len(), 1);
+
+        // a file still being written is left alone until it goes stale
+        let partial = |pid: u32| dir.join(format!("corners1o2n3o.pt.{}-0.partial", pid));
+        let writing = partial(std::process::id());
+        std::fs::write(&writing, b"RRPT").unwrap();
+        let listed = store.list().unwrap();
+        assert!(listed[1].is_partial() && !listed[1].is_abandoned());
+        assert!(store.prune(|_| true).unwrap().is_empty());
+        let stale = SystemTime::now() - 2 * STALE_PARTIAL;
+        let file = std::fs::File::options().write(true).open(&writing).unwrap();
+        file.set_modified(stale).unwrap();
+        assert_eq!(store.prune(|_| true).unwrap(), vec![writing]);
+        if Path::new("/proc").is_dir() {
+            let orphaned = partial(u32::MAX);
+            std::fs::write(&orphaned, b"RRPT").unwrap();
+            assert_eq!(store.prune(|_| true).unwrap(), vec![orphaned]);
+        }
+        assert_eq!(store.list().unwrap().len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let missing = TableStore::new(dir.join("missing")).with_policy(TablePolicy::LoadOnly);
+        assert!(missing.list().unwrap().is_empty());
+        assert!(matches!(
+            missing.tables(&cycle_type),
+            Err(TableError::Missing(_))
+        ));
+    }
+}
@@ -0,0 +1,424 @@
+//! The 48 symmetries of the cube and the symmetry reduction of corner states.
+//!
+//! Conjugating a state by a symmetry, rotating or reflecting the whole cube
+//! before and after it, preserves its cycle type and maps the 18 face turns
+//! onto themselves. The distance from a state to the goal states of a cycle
+//! type is therefore the same for every state in its symmetry class, and a
+//! pruning table only needs one entry per class.
+//!
+//! Corner states are reduced through their permutation: the corner
+//! permutations are split into classes, each with a representative, and a
+//! state is conjugated so that its permutation becomes the representative.
+//! The sym-coordinate of a state is then its class together with the
+//! orientation of the conjugated state.
+
+use crate::cube::{BaseMoveToken, CubeState, Direction, MoveInstance, MoveSequence};
+use crate::move_table::{CORNER_ORIENTATIONS, CORNER_PERMUTATIONS};
+use std::sync::LazyLock;
+
+/// The number of symmetries of the cube, including reflections.
+pub const SYMMETRIES: usize = 48;
+
+/// A symmetry of the cube: an optional reflection through the plane between
+/// the L and R faces, followed by a rotation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symmetry {
+    // the corners of the rotation, as a state
+    rotation: CubeState,
+    rotation_inverse: CubeState,
+    mirrored: bool,
+}
+
+impl Symmetry {
+    /// Conjugates the corners of a state by this symmetry. The edges of the
+    /// result are solved.
+    pub fn conjugate_corners(&self, state: &CubeState) -> CubeState {
+        let state = if self.mirrored {
+            state.mirror_corners()
+        } else {
+            state.clone()
+        };
+        self.rotation_inverse
+            .then_corners(&state)
+            .then_corners(&self.rotation)
+    }
+
+    /// Whether this symmetry reflects the cube.
+    pub fn is_mirrored(&self) -> bool {
+        self.mirrored
+    }
+
+    /// Every symmetry of the cube, starting with the identity.
+    pub fn all() -> Vec<Symmetry> {
+        // a slice turn leaves the corners alone, so on the corners each
+        // whole-cube rotation is a pair of opposite face turns
+        let quarter_turns = |a, b| {
+            let moves = MoveSequence::from(vec![
+                MoveInstance::new(a, Direction::Normal),
+                MoveInstance::new(b, Direction::Prime),
+            ]);
+            CubeState::default().apply_move_instances(&moves)
+        };
+        let generators = [
+            quarter_turns(BaseMoveToken::R, BaseMoveToken::L),
+            quarter_turns(BaseMoveToken::U, BaseMoveToken::D),
+            quarter_turns(BaseMoveToken::F, BaseMoveToken::B),
+        ];
+        let mut rotations = vec![CubeState::default()];
+        let mut i = 0;
+        while i < rotations.len() {
+            for generator in generators.iter() {
+                let rotation = rotations[i].then_corners(generator);
+                if !rotations.contains(&rotation) {
+                    rotations.push(rotation);
+                }
+            }
+            i += 1;
+        }
+        debug_assert_eq!(rotations.len(), SYMMETRIES / 2);
+        [false, true]
+            .into_iter()
+            .flat_map(|mirrored| {
+                rotations.iter().map(move |rotation| Symmetry {
+                    rotation: rotation.clone(),
+                    rotation_inverse: rotation.inverse_corners(),
+                    mirrored,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The symmetry classes of the corner permutations, used to index
+/// symmetry-reduced corner pruning tables.
+pub struct CornerSymmetries {
+    symmetries: Vec<Symmetry>,
+    // for each corner permutation, its class and a symmetry taking it to the
+    // representative of its class
+    class: Vec<u16>,
+    symmetry: Vec<u8>,
+    // the corner permutation index of the representative of each class, and
+    // the number of corner permutations in the class
+    representatives: Vec<u16>,
+    sizes: Vec<u16>,
+    // the symmetries other than the identity that fix the representative of
+    // each class, each with the twist it gives the untwisted representative
+    stabilizers: Vec<Vec<(u8, u8)>>,
+    // Conjugating a state twists its corners as if it were conjugated with
+    // its corners untwisted and then twisted by the conjugate of the pure
+    // twist with its orientation. For each corner permutation, the twist
+    // that conjugating it to its representative gives the untwisted state,
+    // numbered among the few such twists; for each orientation, the
+    // orientation of the conjugate of the pure twist by each symmetry; and
+    // for each numbered twist and orientation, the orientation of their sum.
+    twist: Vec<u8>,
+    conjugate_twists: Vec<[u16; SYMMETRIES]>,
+    twist_sums: Vec<u16>,
+}
+
+/// The corner symmetry classes, computed on first use.
+pub static CORNER_SYMMETRIES: LazyLock<CornerSymmetries> = LazyLock::new(CornerSymmetries::new);
+
+impl CornerSymmetries {
+    fn new() -> Self {
+        let symmetries = Symmetry::all();
+        // a state with no symmetries of its own, to tell symmetries apart
+        let probe = CubeState::default().apply_move_instances(&MoveSequence::from(
+            crate::parser::parse_scramble("R U2 F' L D' B2 R' U").unwrap(),
+        ));
+        let probe = CubeState::from_corner_state_index(probe.corner_state_index());
+        let inverse: Vec<u8> = symmetries
+            .iter()
+            .map(|s| {
+                let conjugated = s.conjugate_corners(&probe);
+                symmetries
+                    .iter()
+                    .position(|t| t.conjugate_corners(&conjugated) == probe)
+                    .unwrap() as u8
+            })
+            .collect();
+
+        let unassigned = u16::MAX;
+        let mut class = vec![unassigned; CORNER_PERMUTATIONS];
+        let mut symmetry = vec![0; CORNER_PERMUTATIONS];
+        let mut representatives = vec![];
+        let mut stabilizers = vec![];
+        for cp_index in 0..CORNER_PERMUTATIONS {
+            if class[cp_index] != unassigned {
+                continue;
+            }
+            let representative = CubeState::from_corner_state_index(cp_index_to_state(cp_index));
+            let mut stabilizer = vec![];
+            for (s, sym) in symmetries.iter().enumerate() {
+                let image = sym.conjugate_corners(&representative);
+                let image_index = image.corner_permutation_index() as usize;
+                if class[image_index] == unassigned {
+                    class[image_index] = representatives.len() as u16;
+                    symmetry[image_index] = inverse[s];
+                } else if image_index == cp_index {
+                    stabilizer.push((s as u8, image.corner_orientation_index()));
+                }
+            }
+            representatives.push(cp_index as u16);
+            stabilizers.push(stabilizer);
+        }
+        let mut sizes = vec![0; representatives.len()];
+        for &c in class.iter() {
+            sizes[c as usize] += 1;
+        }
+
+        let mut twists = vec![];
+        let mut twist_of = |orientation: u16| match twists.iter().position(|&t| t == orientation) {
+            Some(t) => t as u8,
+            None => {
+                twists.push(orientation);
+                (twists.len() - 1) as u8
+            }
+        };
+        let twist = (0..CORNER_PERMUTATIONS)
+            .map(|cp_index| {
+                let state = CubeState::from_corner_state_index(cp_index_to_state(cp_index));
+                let s = symmetry[cp_index] as usize;
+                twist_of(
+                    symmetries[s]
+                        .conjugate_corners(&state)
+                        .corner_orientation_index(),
+                )
+            })
+            .collect();
+        let stabilizers = stabilizers
+            .into_iter()
+            .map(|stabilizer: Vec<(u8, u16)>| {
+                stabilizer
+                    .into_iter()
+                    .map(|(s, orientation)| (s, twist_of(orientation)))
+                    .collect()
+            })
+            .collect();
+        let conjugate_twists = (0..CORNER_ORIENTATIONS)
+            .map(|co_index| {
+                let state = CubeState::from_corner_state_index(co_index as u32);
+                std::array::from_fn(|s| {
+                    symmetries[s]
+                        .conjugate_corners(&state)
+                        .corner_orientation_index()
+                })
+            })
+            .collect();
+        let twist_sums = twists
+            .iter()
+            .flat_map(|&t| {
+                let t = CubeState::from_corner_state_index(t as u32);
+                (0..CORNER_ORIENTATIONS).map(move |co_index| {
+                    let state = CubeState::from_corner_state_index(co_index as u32);
+                    t.then_corners(&state).corner_orientation_index()
+                })
+            })
+            .collect();
+        CornerSymmetries {
+            symmetries,
+            class,
+            symmetry,
+            representatives,
+            sizes,
+            stabilizers,
+            twist,
+            conjugate_twists,
+            twist_sums,
+        }
+    }
+
+    // the orientation of the sum of a twist and the pure twist of an
+    // orientation
+    fn twist_sum(&self, twist: u8, co_index: usize) -> usize {
+        self.twist_sums[twist as usize * CORNER_ORIENTATIONS + co_index] as usize
+    }
+
+    /// The number of symmetry classes of corner permutations.
+    pub fn classes(&self) -> usize {
+        self.representatives.len()
+    }
+
+    /// The number of corner permutations in a class. Each sym-coordinate in
+    /// the class is the sym-coordinate of this many corner states.
+    pub fn class_size(&self, class: usize) -> usize {
+        self.sizes[class] as usize
+    }
+
+    /// The number of entries in a symmetry-reduced corner table.
+    pub fn len(&self) -> usize {
+        self.classes() * CORNER_ORIENTATIONS
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.representatives.is_empty()
+    }
+
+    /// The symmetries of the cube, in the order `sym_coordinate` refers to.
+    pub fn symmetries(&self) -> &[Symmetry] {
+        &self.symmetries
+    }
+
+    /// The class of the corner permutation of a state and the index of a
+    /// symmetry conjugating it to the representative of that class.
+    pub fn class_and_symmetry(&self, state: &CubeState) -> (usize, usize) {
+        let cp_index = state.corner_permutation_index() as usize;
+        (
+            self.class[cp_index] as usize,
+            self.symmetry[cp_index] as usize,
+        )
+    }
+
+    /// Whether the corner permutation of a state is the representative of
+    /// its class.
+    pub fn is_representative(&self, state: &CubeState) -> bool {
+        let cp_index = state.corner_permutation_index();
+        self.representatives[self.class[cp_index as usize] as usize] == cp_index
+    }
+
+    /// Computes the sym-coordinate of the corners of a state, in
+    /// `[0, self.len() - 1]`. It is the coordinate of a state conjugate to
+    /// this one, so it has the same depth in a symmetry-reduced table.
+    ///
+    /// When the representative of the class has symmetries of its own,
+    /// conjugate states can have different sym-coordinates; see
+    /// `equivalents`.
+    pub fn sym_coordinate(&self, state: &CubeState) -> usize {
+        self.sym_coordinate_of(
+            state.corner_permutation_index() as usize,
+            state.corner_orientation_index() as usize,
+        )
+    }
+
+    /// Like `sym_coordinate`, but from the corner permutation and
+    /// orientation indices of a state, through lookup tables rather than by
+    /// conjugating it.
+    pub fn sym_coordinate_of(&self, cp_index: usize, co_index: usize) -> usize {
+        let symmetry = self.symmetry[cp_index] as usize;
+        let co_index = self.conjugate_twists[co_index][symmetry] as usize;
+        self.class[cp_index] as usize * CORNER_ORIENTATIONS
+            + self.twist_sum(self.twist[cp_index], co_index)
+    }
+
+    /// The other sym-coordinates of the states conjugate to the state with
+    /// the given sym-coordinate, which a symmetry-reduced table must give
+    /// the same depth. There are none unless the representative of the class
+    /// has symmetries of its own.
+    pub fn equivalents(&self, sym_coordinate: usize) -> impl Iterator<Item = usize> + '_ {
+        let class = sym_coordinate / CORNER_ORIENTATIONS;
+        let co_index = sym_coordinate % CORNER_ORIENTATIONS;
+        self.stabilizers[class]
+            .iter()
+            .filter_map(move |&(s, twist)| {
+                let conjugated = self.conjugate_twists[co_index][s as usize] as usize;
+                let coordinate = class * CORNER_ORIENTATIONS + self.twist_sum(twist, conjugated);
+                (coordinate != sym_coordinate).then_some(coordinate)
+            })
+    }
+
+    /// The corner permutation index of the representative of the class of a
+    /// sym-coordinate.
+    pub fn representative_permutation(&self, sym_coordinate: usize) -> usize {
+        self.representatives[sym_coordinate / CORNER_ORIENTATIONS] as usize
+    }
+
+    /// A state with the given sym-coordinate, whose corner permutation is
+    /// the representative of its class.
+    pub fn representative(&self, sym_coordinate: usize) -> CubeState {
+        let cp_index = self.representative_permutation(sym_coordinate);
+        CubeState::from_corner_state_index(
+            cp_index_to_state(cp_index) + (sym_coordinate % CORNER_ORIENTATIONS) as u32,
+        )
+    }
+}
+
+// the corner state index of a corner permutation with no twists
+fn cp_index_to_state(cp_index: usize) -> u32 {
+    (cp_index * CORNER_ORIENTATIONS) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cube, parser};
+
+    #[test]
+    fn test_symmetries_preserve_moves() {
+        let symmetries = Symmetry::all();
+        assert_eq!(symmetries.len(), SYMMETRIES);
+        let solved = CubeState::default();
+        // reflection maps each face turn to a face turn
+        let corners =
+            |state: CubeState| CubeState::from_corner_state_index(state.corner_state_index());
+        let turn =
+            |moves: &str| {
+                corners(solved.apply_move_instances(&MoveSequence::from(
+                    parser::parse_scramble(moves).unwrap(),
+                )))
+            };
+        assert_eq!(turn("R").mirror_corners(), turn("L'"));
+        assert_eq!(turn("U").mirror_corners(), turn("U'"));
+        assert_eq!(turn("F").mirror_corners(), turn("F'"));
+        // every symmetry maps each face turn to one of the 18 face turns
+        let corner_moves: Vec<CubeState> = cube::ALL_MOVES
+            .iter()
+            .map(|m| corners(solved.apply_move_instance(m)))
+            .collect();
+        for symmetry in symmetries.iter() {
+            assert_eq!(symmetry.conjugate_corners(&solved), solved);
+            for m in corner_moves.iter() {
+                assert!(corner_moves.contains(&symmetry.conjugate_corners(m)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_sym_coordinate() {
+        let symmetries = &*CORNER_SYMMETRIES;
+        assert_eq!(symmetries.classes(), 984);
+        let sizes = (0..symmetries.classes()).map(|class| symmetries.class_size(class));
+        assert_eq!(sizes.sum::<usize>(), CORNER_PERMUTATIONS);
+        let state = CubeState::default().apply_move_instances(&MoveSequence::from(
+            parser::parse_scramble("R U F' L2 D B").unwrap(),
+        ));
+        let coordinate = symmetries.sym_coordinate(&state);
+        let (class, _) = symmetries.class_and_symmetry(&state);
+        let equivalents: Vec<usize> = symmetries.equivalents(coordinate).collect();
+        for symmetry in symmetries.symmetries() {
+            let conjugated = symmetry.conjugate_corners(&state);
+            assert_eq!(symmetries.class_and_symmetry(&conjugated).0, class);
+            let conjugated = symmetries.sym_coordinate(&conjugated);
+            assert!(conjugated == coordinate || equivalents.contains(&conjugated));
+        }
+        // the solved state is fixed by every symmetry
+        let solved = symmetries.sym_coordinate(&CubeState::default());
+        assert_eq!(symmetries.equivalents(solved).count(), 0);
+        let twisted = CubeState::from_corner_state_index(1);
+        let twisted = symmetries.sym_coordinate(&twisted);
+        assert!(symmetries.equivalents(twisted).count() > 0);
+        let representative = symmetries.representative(coordinate);
+        assert_eq!(symmetries.sym_coordinate(&representative), coordinate);
+        // the lookup tables agree with conjugating the state
+        for index in (0..CORNER_PERMUTATIONS * CORNER_ORIENTATIONS).step_by(7919) {
+            let state = CubeState::from_corner_state_index(index as u32);
+            let (class, symmetry) = symmetries.class_and_symmetry(&state);
+            let reduced = symmetries.symmetries()[symmetry].conjugate_corners(&state);
+            assert_eq!(
+                symmetries.sym_coordinate(&state),
+                class * CORNER_ORIENTATIONS + reduced.corner_orientation_index() as usize
+            );
+            let representative = symmetries.representative(symmetries.sym_coordinate(&state));
+            for equivalent in symmetries.equivalents(symmetries.sym_coordinate(&state)) {
+                let conjugated = symmetries.symmetries().iter().any(|symmetry| {
+                    let conjugated = symmetry.conjugate_corners(&representative);
+                    conjugated.corner_permutation_index()
+                        == representative.corner_permutation_index()
+                        && class * CORNER_ORIENTATIONS
+                            + conjugated.corner_orientation_index() as usize
+                            == equivalent
+                });
+                assert!(conjugated);
+            }
+        }
+    }
+}
@@ -0,0 +1,477 @@
+//! A module for the compact storage of pruning tables.
+//!
+//! Pruning table entries are small depths, so rather than spending a whole
+//! byte on each, a `PackedTable` stores several entries per byte. With
+//! `Packing::Nibble` each entry is a 4-bit depth, which halves the memory
+//! and disk space needed for every table. With `Packing::Mod3` each entry is
+//! only the depth modulo 3 in 2 bits, quartering it; the exact depth of a
+//! state is then recovered from the depth of a neighbour, since neighbouring
+//! states differ in depth by at most one.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// The value of a `Packing::Nibble` entry that has not been filled in yet.
+pub const UNFILLED: u8 = 0xF;
+
+/// The largest depth that can be stored in a `Packing::Nibble` table.
+pub const MAX_DEPTH: u8 = UNFILLED - 1;
+
+/// How the entries of a `PackedTable` are stored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum Packing {
+    /// Exact depths, two 4-bit entries per byte.
+    #[default]
+    Nibble,
+    /// Depths modulo 3, four 2-bit entries per byte.
+    Mod3,
+}
+
+impl Packing {
+    /// The number of bits used by each entry.
+    pub fn bits(self) -> usize {
+        match self {
+            Packing::Nibble => 4,
+            Packing::Mod3 => 2,
+        }
+    }
+
+    /// The value of an entry that has not been filled in yet.
+    pub fn unfilled(self) -> u8 {
+        (1 << self.bits()) - 1
+    }
+
+    /// The value stored for a state at the given depth.
+    pub fn encode(self, depth: u8) -> u8 {
+        match self {
+            Packing::Nibble => depth,
+            Packing::Mod3 => depth % 3,
+        }
+    }
+
+    fn entries_per_byte(self) -> usize {
+        8 / self.bits()
+    }
+}
+
+/// Recovers the exact depth of a state from its `Packing::Mod3` entry and
+/// the exact depth of one of its neighbours.
+pub fn depth_from_neighbour(residue: u8, neighbour_depth: u8) -> u8 {
+    match (residue + 3 - neighbour_depth % 3) % 3 {
+        0 => neighbour_depth,
+        1 => neighbour_depth + 1,
+        _ => neighbour_depth - 1,
+    }
+}
+
+/// A table of small entries packed into bytes, as described by a `Packing`.
+///
+/// Entry `i` is stored in byte `i / k` for `k` entries per byte, with lower
+/// entries in the lower bits.
+///
+/// With the `mmap` feature, a table loaded from a file can instead borrow
+/// the file's pages through a memory map, see `table_file::load`.
+#[derive(Clone, Debug)]
+pub struct PackedTable {
+    packing: Packing,
+    len: usize,
+    data: Storage,
+}
+
+// The bytes of a table.
+#[derive(Debug)]
+enum Storage {
+    Owned(Vec<u8>),
+    #[cfg(feature = "mmap")]
+    Mapped {
+        map: memmap2::Mmap,
+        range: std::ops::Range<usize>,
+    },
+}
+
+impl Storage {
+    fn bytes(&self) -> &[u8] {
+        match self {
+            Storage::Owned(data) => data,
+            #[cfg(feature = "mmap")]
+            Storage::Mapped { map, range } => &map[range.clone()],
+        }
+    }
+
+    // copies a mapped table into memory before it is modified
+    fn bytes_mut(&mut self) -> &mut [u8] {
+        #[cfg(feature = "mmap")]
+        if let Storage::Mapped { .. } = self {
+            *self = Storage::Owned(self.bytes().to_vec());
+        }
+        match self {
+            Storage::Owned(data) => data,
+            #[cfg(feature = "mmap")]
+            Storage::Mapped { .. } => unreachable!(),
+        }
+    }
+}
+
+impl Clone for Storage {
+    fn clone(&self) -> Self {
+        Storage::Owned(self.bytes().to_vec())
+    }
+}
+
+impl PartialEq for PackedTable {
+    fn eq(&self, other: &Self) -> bool {
+        self.packing == other.packing
+            && self.len == other.len
+            && self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl Eq for PackedTable {}
+
+impl PackedTable {
+    /// Creates a table of `len` entries, all unfilled.
+    pub fn new(len: usize, packing: Packing) -> Self {
+        Self {
+            packing,
+            len,
+            data: Storage::Owned(vec![0xFF; Self::packed_len(len, packing)]),
+        }
+    }
+
+    /// The number of bytes needed to store `len` packed entries.
+    pub fn packed_len(len: usize, packing: Packing) -> usize {
+        len.div_ceil(packing.entries_per_byte())
+    }
+
+    /// Wraps already packed bytes, as returned by `as_bytes`.
+    ///
+    /// Returns `None` if `data` is not the right size for `len` entries.
+    pub fn from_packed(len: usize, packing: Packing, data: Vec<u8>) -> Option<Self> {
+        (data.len() == Self::packed_len(len, packing)).then_some(Self {
+            packing,
+            len,
+            data: Storage::Owned(data),
+        })
+    }
+
+    /// Wraps packed bytes within a memory map, as written by
+    /// `table_file::write`.
+    ///
+    /// Returns `None` if `range` is not the right size for `len` entries.
+    #[cfg(feature = "mmap")]
+    pub fn from_mapped(
+        len: usize,
+        packing: Packing,
+        map: memmap2::Mmap,
+        range: std::ops::Range<usize>,
+    ) -> Option<Self> {
+        (range.end <= map.len() && range.len() == Self::packed_len(len, packing)).then_some(Self {
+            packing,
+            len,
+            data: Storage::Mapped { map, range },
+        })
+    }
+
+    /// Whether the table borrows its entries from a memory-mapped file.
+    pub fn is_mapped(&self) -> bool {
+        match self.data {
+            Storage::Owned(_) => false,
+            #[cfg(feature = "mmap")]
+            Storage::Mapped { .. } => true,
+        }
+    }
+
+    /// Packs a table with one byte per entry, as pruning tables used to be
+    /// stored. Entries that don't fit in 4 bits become unfilled.
+    pub fn from_unpacked(entries: &[u8]) -> Self {
+        let mut table = Self::new(entries.len(), Packing::Nibble);
+        for (index, &entry) in entries.iter().enumerate() {
+            table.set(index, entry.min(UNFILLED));
+        }
+        table
+    }
+
+    /// Converts a `Packing::Nibble` table into a `Packing::Mod3` table with
+    /// the same depths. The new table is held in memory, and converting a
+    /// memory-mapped table reads every page of it.
+    pub fn to_mod3(&self) -> Self {
+        let mut table = Self::new(self.len, Packing::Mod3);
+        for (index, entry) in self.iter().enumerate() {
+            if entry != self.packing.unfilled() {
+                table.set(index, Packing::Mod3.encode(entry));
+            }
+        }
+        table
+    }
+
+    pub fn packing(&self) -> Packing {
+        self.packing
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the entry at `index`.
+    pub fn get(&self, index: usize) -> u8 {
+        let per_byte = self.packing.entries_per_byte();
+        let shift = (index % per_byte) * self.packing.bits();
+        (self.data.bytes()[index / per_byte] >> shift) & self.packing.unfilled()
+    }
+
+    /// Sets the entry at `index` to `value`, which must fit in the packing.
+    /// A memory-mapped table is first copied into memory, as a whole.
+    pub fn set(&mut self, index: usize, value: u8) {
+        let mask = self.packing.unfilled();
+        debug_assert!(value <= mask);
+        let per_byte = self.packing.entries_per_byte();
+        let shift = (index % per_byte) * self.packing.bits();
+        let byte = &mut self.data.bytes_mut()[index / per_byte];
+        *byte = (*byte & !(mask << shift)) | (value << shift);
+    }
+
+    /// Iterates over the entries of the table in order.
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..self.len).map(|index| self.get(index))
+    }
+
+    /// The number of entries with each stored value, from 0 up to the
+    /// unfilled value of the packing.
+    pub fn histogram(&self) -> Vec<u64> {
+        let mask = self.packing.unfilled();
+        let per_byte = self.packing.entries_per_byte();
+        let mut histogram = vec![0; mask as usize + 1];
+        for (i, &byte) in self.data.bytes().iter().enumerate() {
+            // the last byte may be padded past the end of the table
+            for k in 0..per_byte.min(self.len - i * per_byte) {
+                histogram[((byte >> (k * self.packing.bits())) & mask) as usize] += 1;
+            }
+        }
+        histogram
+    }
+
+    /// The packed bytes of the table, as written to disk.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.data.bytes()
+    }
+}
+
+/// Statistics of the depths of the states in a pruning table, which predict
+/// how much a search using it will have to expand.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TableStats {
+    /// The number of states at each depth, up to the deepest.
+    pub histogram: Vec<u64>,
+    /// The number of states from which no goal state can be reached.
+    pub unreachable: u64,
+}
+
+impl TableStats {
+    /// The statistics of a `Packing::Nibble` table with one entry per state,
+    /// from the number of entries with each value as given by
+    /// `PackedTable::histogram`.
+    pub fn from_histogram(histogram: &[u64]) -> Self {
+        let mut stats = TableStats::default();
+        for (depth, &count) in histogram.iter().enumerate() {
+            if depth == UNFILLED as usize {
+                stats.unreachable += count;
+            } else if count > 0 {
+                stats.histogram.resize(depth + 1, 0);
+                stats.histogram[depth] = count;
+            }
+        }
+        stats
+    }
+
+    /// The statistics of a `Packing::Nibble` table whose entry at each
+    /// index stands for `weight(index)` states.
+    pub fn from_weighted(table: &PackedTable, weight: impl Fn(usize) -> u64) -> Self {
+        let mut histogram = vec![0; UNFILLED as usize + 1];
+        for (index, depth) in table.iter().enumerate() {
+            histogram[depth as usize] += weight(index);
+        }
+        Self::from_histogram(&histogram)
+    }
+
+    /// The number of states from which a goal state can be reached.
+    pub fn states(&self) -> u64 {
+        self.histogram.iter().sum()
+    }
+
+    /// The number of goal states.
+    pub fn goals(&self) -> u64 {
+        self.histogram.first().copied().unwrap_or(0)
+    }
+
+    /// The largest depth of a state, or `None` if there are no goal states.
+    pub fn max_depth(&self) -> Option<u8> {
+        self.histogram.len().checked_sub(1).map(|depth| depth as u8)
+    }
+
+    /// The mean depth of the states from which a goal state can be reached,
+    /// which is the mean heuristic value the table gives them.
+    pub fn mean_depth(&self) -> f64 {
+        let total: u64 = self
+            .histogram
+            .iter()
+            .enumerate()
+            .map(|(depth, &count)| depth as u64 * count)
+            .sum();
+        total as f64 / self.states().max(1) as f64
+    }
+}
+
+impl std::fmt::Display for TableStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} goal states, mean depth {:.3}, max depth ",
+            self.goals(),
+            self.mean_depth()
+        )?;
+        match self.max_depth() {
+            Some(depth) => write!(f, "{}", depth)?,
+            None => write!(f, "-")?,
+        }
+        if self.unreachable > 0 {
+            write!(f, ", {} unreachable", self.unreachable)?;
+        }
+        Ok(())
+    }
+}
+
+/// A `PackedTable` whose entries can be filled in from several threads at
+/// once, as during parallel table generation.
+pub struct AtomicPackedTable {
+    packing: Packing,
+    len: usize,
+    data: Vec<AtomicU8>,
+}
+
+impl AtomicPackedTable {
+    /// Creates a table of `len` entries, all unfilled.
+    pub fn new(len: usize, packing: Packing) -> Self {
+        Self {
+            packing,
+            len,
+            data: (0..PackedTable::packed_len(len, packing))
+                .map(|_| AtomicU8::new(0xFF))
+                .collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the entry at `index`.
+    pub fn get(&self, index: usize) -> u8 {
+        let per_byte = self.packing.entries_per_byte();
+        let shift = (index % per_byte) * self.packing.bits();
+        (self.data[index / per_byte].load(Ordering::Relaxed) >> shift) & self.packing.unfilled()
+    }
+
+    /// Sets the entry at `index` to `value` if it is unfilled, returning
+    /// whether it was. Entries sharing a byte can be filled concurrently.
+    pub fn fill(&self, index: usize, value: u8) -> bool {
+        let mask = self.packing.unfilled();
+        debug_assert!(value < mask);
+        let per_byte = self.packing.entries_per_byte();
+        let shift = (index % per_byte) * self.packing.bits();
+        self.data[index / per_byte]
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |byte| {
+                ((byte >> shift) & mask == mask).then_some(byte & !((mask ^ value) << shift))
+            })
+            .is_ok()
+    }
+
+    /// Converts into an ordinary `PackedTable` once all writers are done.
+    pub fn into_table(self) -> PackedTable {
+        PackedTable {
+            packing: self.packing,
+            len: self.len,
+            data: Storage::Owned(self.data.into_iter().map(AtomicU8::into_inner).collect()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packing_round_trip() {
+        let entries: Vec<u8> = (0..101).map(|i| (i * 7 % 13) as u8).collect();
+        let table = PackedTable::from_unpacked(&entries);
+        assert_eq!(table.len(), 101);
+        assert_eq!(table.as_bytes().len(), 51);
+        for (index, &entry) in entries.iter().enumerate() {
+            assert_eq!(table.get(index), entry);
+        }
+
+        let mod3 = table.to_mod3();
+        assert_eq!(mod3.as_bytes().len(), 26);
+        for (index, &entry) in entries.iter().enumerate() {
+            assert_eq!(mod3.get(index), entry % 3);
+        }
+
+        let mut unfilled = PackedTable::from_unpacked(&[3, 255, 0]);
+        assert_eq!(unfilled.get(1), UNFILLED);
+        assert_eq!(unfilled.to_mod3().get(1), Packing::Mod3.unfilled());
+        unfilled.set(1, 5);
+        assert_eq!(
+            (unfilled.get(0), unfilled.get(1), unfilled.get(2)),
+            (3, 5, 0)
+        );
+        assert_eq!(
+            PackedTable::from_packed(3, Packing::Nibble, unfilled.as_bytes().to_vec()),
+            Some(unfilled)
+        );
+        assert_eq!(
+            PackedTable::from_packed(5, Packing::Nibble, vec![0; 2]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_atomic_fill() {
+        let table = AtomicPackedTable::new(5, Packing::Nibble);
+        assert!(table.fill(3, 7));
+        assert!(!table.fill(3, 2));
+        assert!(table.fill(2, 0));
+        let table = table.into_table();
+        assert_eq!(table.iter().collect::<Vec<_>>(), [15, 15, 0, 7, 15]);
+    }
+
+    #[test]
+    fn test_table_stats() {
+        let table = PackedTable::from_unpacked(&[0, 1, 2, 1, 0, 1, UNFILLED]);
+        let stats = TableStats::from_histogram(&table.histogram());
+        assert_eq!(stats.histogram, [2, 3, 1]);
+        assert_eq!(stats.unreachable, 1);
+        assert_eq!(stats.states(), 6);
+        assert_eq!(stats.goals(), 2);
+        assert_eq!(stats.max_depth(), Some(2));
+        assert_eq!(stats.mean_depth(), 5.0 / 6.0);
+        let weighted = TableStats::from_weighted(&table, |index| index as u64 + 1);
+        assert_eq!(weighted.histogram, [6, 12, 3]);
+        assert_eq!(weighted.unreachable, 7);
+        assert_eq!(TableStats::from_histogram(&[0; 16]).max_depth(), None);
+    }
+
+    #[test]
+    fn test_depth_from_neighbour() {
+        for depth in 1..12 {
+            for neighbour_depth in [depth - 1, depth, depth + 1] {
+                assert_eq!(depth_from_neighbour(depth % 3, neighbour_depth), depth);
+            }
+        }
+    }
+}
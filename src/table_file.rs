@@ -0,0 +1,686 @@
+//! The on-disk format of pruning tables.
+//!
+//! A table file describes its own contents, so that a file generated for
+//! another cycle type, coordinate or packing, or one that was cut short or
+//! corrupted, is refused on load rather than silently giving wrong bounds.
+//! All integers are little-endian:
+//!
+//! | field        | encoding                                                  |
+//! |--------------|-----------------------------------------------------------|
+//! | magic        | the bytes `RRPT`                                          |
+//! | version      | `u16`, currently 3                                        |
+//! | coordinate   | `u8` tag, followed for partial edges by a `u8` count and the edges, and for patterns by the corners and edges likewise, each followed by a `u8` flag for orientation, and a `u8` flag and `u64` target |
+//! | packing      | `u8`, 0 for `Packing::Nibble` and 1 for `Packing::Mod3`   |
+//! | depth bound  | `u8`, the bound of a depth-bounded table or 255 for none; absent in version 1 |
+//! | targets      | `u16` length and the canonical `Targets` as UTF-8, which for a single cycle type is the `CycleType` itself |
+//! | entries      | `u64` number of entries                                   |
+//! | histogram    | `u16` length and a `u64` count of the entries with each stored value |
+//! | stats        | `u16` length and a `u64` count of the states at each depth, then a `u64` count of unreachable states; absent before version 3 |
+//! | data         | `u64` length and the packed entries                       |
+//! | checksum     | `u32` CRC-32 of everything before it                      |
+
+use crate::pattern::Pattern;
+use crate::table::{PackedTable, Packing, TableStats};
+use crate::Targets;
+use std::fmt;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const MAGIC: &[u8; 4] = b"RRPT";
+const VERSION: u16 = 3;
+// a depth bound of none
+const UNBOUNDED: u8 = u8::MAX;
+
+/// How the states of a table are numbered.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Coordinate {
+    /// `symmetry::CornerSymmetries::sym_coordinate`.
+    CornerSymmetry,
+    /// `CubeState::edge_orientation_index`.
+    EdgeOrientation,
+    /// `CubeState::edge_permutation_index`.
+    EdgePermutation,
+    /// `CubeState::partial_edge_index` over the given edges.
+    PartialEdges(Vec<u8>),
+    /// `CubeState::pattern_index` over the given pattern. With a target the
+    /// goal is the one entry at that index, and otherwise the projections of
+    /// the cycle type of the table.
+    Pattern {
+        pattern: Pattern,
+        target: Option<usize>,
+    },
+}
+
+impl fmt::Display for Coordinate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Coordinate::CornerSymmetry => write!(f, "corner symmetry classes"),
+            Coordinate::EdgeOrientation => write!(f, "edge orientation"),
+            Coordinate::EdgePermutation => write!(f, "edge permutation"),
+            Coordinate::PartialEdges(edges) => write!(f, "partial edges {:?}", edges),
+            Coordinate::Pattern { pattern, target } => {
+                write!(f, "pattern {}", pattern)?;
+                match target {
+                    Some(target) => write!(f, " towards entry {}", target),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+/// Everything a table file records about its contents.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableHeader {
+    pub coordinate: Coordinate,
+    /// The part of the targets the table is for, such as only the corner
+    /// partitions for a corner table.
+    pub targets: Targets,
+    pub packing: Packing,
+    /// For a table whose generation stopped at a depth `k`, the bound `k`.
+    /// Every deeper state is stored as `k + 1`.
+    pub depth_bound: Option<u8>,
+    /// The number of entries.
+    pub len: usize,
+}
+
+impl TableHeader {
+    /// This header with the given depth bound.
+    pub fn with_depth_bound(self, depth_bound: Option<u8>) -> Self {
+        TableHeader {
+            depth_bound,
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for TableHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} table of {} {:?} entries for {}",
+            self.coordinate, self.len, self.packing, self.targets
+        )?;
+        if let Some(bound) = self.depth_bound {
+            write!(f, " bounded at depth {}", bound)?;
+        }
+        Ok(())
+    }
+}
+
+/// The reasons a table file can be refused.
+#[derive(Debug)]
+pub enum TableFileError {
+    Io(std::io::Error),
+    /// The file does not start with the magic number.
+    NotATable,
+    UnsupportedVersion(u16),
+    /// The file ends before its header says it should.
+    Truncated,
+    /// The file was written for a different table.
+    Mismatch {
+        expected: Box<TableHeader>,
+        found: Box<TableHeader>,
+    },
+    /// The header could not be decoded.
+    Malformed(String),
+    /// The contents do not match the stored checksum.
+    Checksum {
+        expected: u32,
+        actual: u32,
+    },
+    /// The entries do not match the stored histogram.
+    Histogram,
+}
+
+impl fmt::Display for TableFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableFileError::Io(err) => write!(f, "{}", err),
+            TableFileError::NotATable => write!(f, "not a pruning table file"),
+            TableFileError::UnsupportedVersion(version) => {
+                write!(f, "unsupported table format version {}", version)
+            }
+            TableFileError::Truncated => write!(f, "file is truncated"),
+            TableFileError::Mismatch { expected, found } => {
+                write!(f, "expected a {}, found a {}", expected, found)
+            }
+            TableFileError::Malformed(reason) => write!(f, "malformed header: {}", reason),
+            TableFileError::Checksum { expected, actual } => write!(
+                f,
+                "checksum mismatch: expected {:08x}, found {:08x}",
+                expected, actual
+            ),
+            TableFileError::Histogram => write!(f, "entries do not match the stored histogram"),
+        }
+    }
+}
+
+impl std::error::Error for TableFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TableFileError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for TableFileError {
+    fn from(err: std::io::Error) -> Self {
+        TableFileError::Io(err)
+    }
+}
+
+/// The header, histogram and statistics of a table file, as returned by
+/// `read_info`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableInfo {
+    pub header: TableHeader,
+    /// The number of entries with each stored value.
+    pub histogram: Vec<u64>,
+    /// The number of states at each depth, which differs from `histogram`
+    /// when an entry stands for several states or only stores its depth
+    /// modulo 3. Files written before version 3 don't have them.
+    pub stats: Option<TableStats>,
+    pub checksum: u32,
+}
+
+/// Encodes a table, its header and the statistics of its states in the
+/// table file format.
+pub fn encode(header: &TableHeader, table: &PackedTable, stats: &TableStats) -> Vec<u8> {
+    let data = table.as_bytes();
+    let mut bytes = Vec::with_capacity(data.len() + 256);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    match &header.coordinate {
+        Coordinate::CornerSymmetry => bytes.push(0),
+        Coordinate::EdgeOrientation => bytes.push(1),
+        Coordinate::EdgePermutation => bytes.push(2),
+        Coordinate::PartialEdges(edges) => {
+            bytes.push(3);
+            bytes.push(edges.len() as u8);
+            bytes.extend_from_slice(edges);
+        }
+        Coordinate::Pattern { pattern, target } => {
+            bytes.push(4);
+            bytes.push(pattern.corners().len() as u8);
+            bytes.extend_from_slice(pattern.corners());
+            bytes.push(pattern.corner_orientation() as u8);
+            bytes.push(pattern.edges().len() as u8);
+            bytes.extend_from_slice(pattern.edges());
+            bytes.push(pattern.edge_orientation() as u8);
+            bytes.push(target.is_some() as u8);
+            bytes.extend_from_slice(&(target.unwrap_or(0) as u64).to_le_bytes());
+        }
+    }
+    bytes.push(match header.packing {
+        Packing::Nibble => 0,
+        Packing::Mod3 => 1,
+    });
+    bytes.push(header.depth_bound.unwrap_or(UNBOUNDED));
+    let targets = header.targets.to_string();
+    bytes.extend_from_slice(&(targets.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(targets.as_bytes());
+    bytes.extend_from_slice(&(header.len as u64).to_le_bytes());
+    let histogram = table.histogram();
+    bytes.extend_from_slice(&(histogram.len() as u16).to_le_bytes());
+    for count in histogram {
+        bytes.extend_from_slice(&count.to_le_bytes());
+    }
+    bytes.extend_from_slice(&(stats.histogram.len() as u16).to_le_bytes());
+    for count in stats.histogram.iter() {
+        bytes.extend_from_slice(&count.to_le_bytes());
+    }
+    bytes.extend_from_slice(&stats.unreachable.to_le_bytes());
+    bytes.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(data);
+    let checksum = crc32fast::hash(&bytes);
+    bytes.extend_from_slice(&checksum.to_le_bytes());
+    bytes
+}
+
+/// Writes a table, its header and the statistics of its states to `path`.
+///
+/// The file is written next to `path` and then renamed over it, so that a
+/// reader never sees a partly written file and a memory-mapped table is
+/// never changed underneath its users.
+pub fn write(
+    path: impl AsRef<Path>,
+    header: &TableHeader,
+    table: &PackedTable,
+    stats: &TableStats,
+) -> std::io::Result<()> {
+    let path = path.as_ref();
+    // unique to this write, so that concurrent writers never share a file
+    static WRITES: AtomicUsize = AtomicUsize::new(0);
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(format!(
+        ".{}-{}.partial",
+        std::process::id(),
+        WRITES.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::write(&partial, encode(header, table, stats))?;
+    std::fs::rename(&partial, path)
+}
+
+/// Whether `bytes` start like a table file, as opposed to a raw table
+/// written before the format existed.
+pub fn has_magic(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+// A cursor over the bytes of a table file.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], TableFileError> {
+        let end = self
+            .position
+            .checked_add(n)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or(TableFileError::Truncated)?;
+        let taken = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, TableFileError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, TableFileError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, TableFileError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, TableFileError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+// Decodes a table file up to its data, returning its info and the range of
+// the data, after verifying the checksum if `verify_checksum` is set.
+fn decode_info(
+    bytes: &[u8],
+    verify_checksum: bool,
+) -> Result<(TableInfo, std::ops::Range<usize>), TableFileError> {
+    let mut reader = Reader { bytes, position: 0 };
+    if reader
+        .take(MAGIC.len())
+        .map_err(|_| TableFileError::NotATable)?
+        != MAGIC
+    {
+        return Err(TableFileError::NotATable);
+    }
+    let version = reader.u16()?;
+    if !(1..=VERSION).contains(&version) {
+        return Err(TableFileError::UnsupportedVersion(version));
+    }
+    let coordinate = match reader.u8()? {
+        0 => Coordinate::CornerSymmetry,
+        1 => Coordinate::EdgeOrientation,
+        2 => Coordinate::EdgePermutation,
+        3 => {
+            let k = reader.u8()? as usize;
+            Coordinate::PartialEdges(reader.take(k)?.to_vec())
+        }
+        4 => {
+            let j = reader.u8()? as usize;
+            let corners = reader.take(j)?;
+            let corner_orientation = reader.u8()? != 0;
+            let k = reader.u8()? as usize;
+            let edges = reader.take(k)?;
+            let edge_orientation = reader.u8()? != 0;
+            let has_target = reader.u8()? != 0;
+            let target = reader.u64()? as usize;
+            let pattern = Pattern::new()
+                .with_corners(corners, corner_orientation)
+                .with_edges(edges, edge_orientation);
+            if pattern.validate().is_err() {
+                return Err(TableFileError::Malformed(format!("pattern {}", pattern)));
+            }
+            Coordinate::Pattern {
+                pattern,
+                target: has_target.then_some(target),
+            }
+        }
+        tag => return Err(TableFileError::Malformed(format!("coordinate {}", tag))),
+    };
+    let packing = match reader.u8()? {
+        0 => Packing::Nibble,
+        1 => Packing::Mod3,
+        tag => return Err(TableFileError::Malformed(format!("packing {}", tag))),
+    };
+    let depth_bound = match version {
+        1 => None,
+        _ => Some(reader.u8()?).filter(|&bound| bound != UNBOUNDED),
+    };
+    let targets_len = reader.u16()? as usize;
+    let targets = std::str::from_utf8(reader.take(targets_len)?)
+        .ok()
+        .and_then(|targets| targets.parse().ok())
+        .ok_or_else(|| TableFileError::Malformed("targets".to_string()))?;
+    let len = reader.u64()? as usize;
+    if let Coordinate::Pattern { pattern, target } = &coordinate {
+        if len != pattern.size() || target.is_some_and(|target| target >= len) {
+            return Err(TableFileError::Malformed(format!(
+                "{} entries for pattern {}",
+                len, pattern
+            )));
+        }
+    }
+    let histogram_len = reader.u16()? as usize;
+    let histogram = (0..histogram_len)
+        .map(|_| reader.u64())
+        .collect::<Result<Vec<_>, _>>()?;
+    let stats = match version {
+        1 | 2 => None,
+        _ => {
+            let depths = reader.u16()? as usize;
+            let histogram = (0..depths)
+                .map(|_| reader.u64())
+                .collect::<Result<Vec<_>, _>>()?;
+            let unreachable = reader.u64()?;
+            Some(TableStats {
+                histogram,
+                unreachable,
+            })
+        }
+    };
+    let data_len = reader.u64()? as usize;
+    let data_start = reader.position;
+    reader.take(data_len)?;
+    let data = data_start..reader.position;
+    let checksum = reader.u32()?;
+    if verify_checksum {
+        let actual = crc32fast::hash(&bytes[..reader.position - 4]);
+        if checksum != actual {
+            return Err(TableFileError::Checksum {
+                expected: checksum,
+                actual,
+            });
+        }
+    }
+    if reader.position != bytes.len() {
+        return Err(TableFileError::Malformed("trailing bytes".to_string()));
+    }
+    let header = TableHeader {
+        coordinate,
+        targets,
+        packing,
+        depth_bound,
+        len,
+    };
+    Ok((
+        TableInfo {
+            header,
+            histogram,
+            stats,
+            checksum,
+        },
+        data,
+    ))
+}
+
+fn check_header(found: TableHeader, expected: &TableHeader) -> Result<TableHeader, TableFileError> {
+    if &found == expected {
+        Ok(found)
+    } else {
+        Err(TableFileError::Mismatch {
+            expected: Box::new(expected.clone()),
+            found: Box::new(found),
+        })
+    }
+}
+
+/// Decodes a table file, checking that it holds the table described by
+/// `expected` and that its checksum and histogram match its entries.
+pub fn decode(
+    bytes: Vec<u8>,
+    expected: &TableHeader,
+) -> Result<(TableInfo, PackedTable), TableFileError> {
+    decode_table(bytes, Some(expected))
+}
+
+// Decodes a table file as `decode` does, checking which table it holds only
+// if there is an `expected` header.
+fn decode_table(
+    bytes: Vec<u8>,
+    expected: Option<&TableHeader>,
+) -> Result<(TableInfo, PackedTable), TableFileError> {
+    let (info, data) = decode_info(&bytes, true)?;
+    let header = match expected {
+        Some(expected) => check_header(info.header, expected)?,
+        None => info.header,
+    };
+    let info = TableInfo { header, ..info };
+    let mut bytes = bytes;
+    bytes.truncate(data.end);
+    bytes.drain(..data.start);
+    let table = PackedTable::from_packed(info.header.len, info.header.packing, bytes)
+        .ok_or(TableFileError::Truncated)?;
+    if table.histogram() != info.histogram {
+        return Err(TableFileError::Histogram);
+    }
+    Ok((info, table))
+}
+
+/// Reads the table file at `path`, checking it as `decode` does.
+pub fn read(
+    path: impl AsRef<Path>,
+    expected: &TableHeader,
+) -> Result<(TableInfo, PackedTable), TableFileError> {
+    decode(std::fs::read(path)?, expected)
+}
+
+/// Reads the table file at `path`, whichever table it holds, checking its
+/// checksum and histogram as `decode` does.
+pub fn read_any(path: impl AsRef<Path>) -> Result<(TableInfo, PackedTable), TableFileError> {
+    decode_table(std::fs::read(path)?, None)
+}
+
+/// Memory-maps the table file at `path`, checking its header as `decode`
+/// does. Neither the checksum nor the histogram is checked, since either
+/// would read every page of the file and defeat the mapping; `read`, as
+/// used by `pruning::verify_file`, checks both.
+#[cfg(feature = "mmap")]
+pub fn map(
+    path: impl AsRef<Path>,
+    expected: &TableHeader,
+) -> Result<(TableInfo, PackedTable), TableFileError> {
+    let file = std::fs::File::open(path)?;
+    // SAFETY: `write` replaces table files by renaming a new file over them,
+    // so a mapped file is never modified while it is in use
+    let map = unsafe { memmap2::Mmap::map(&file)? };
+    let (info, data) = decode_info(&map, false)?;
+    let info = TableInfo {
+        header: check_header(info.header, expected)?,
+        ..info
+    };
+    let table = PackedTable::from_mapped(expected.len, expected.packing, map, data)
+        .ok_or(TableFileError::Truncated)?;
+    Ok((info, table))
+}
+
+/// Loads the table file at `path`, memory-mapping it with the `mmap`
+/// feature and reading it into memory otherwise.
+pub fn load(
+    path: impl AsRef<Path>,
+    expected: &TableHeader,
+) -> Result<(TableInfo, PackedTable), TableFileError> {
+    #[cfg(feature = "mmap")]
+    return map(path, expected);
+    #[cfg(not(feature = "mmap"))]
+    read(path, expected)
+}
+
+/// Reads the header and histogram of the table file at `path`, verifying
+/// its checksum but not which table it holds.
+pub fn read_info(path: impl AsRef<Path>) -> Result<TableInfo, TableFileError> {
+    Ok(decode_info(&std::fs::read(path)?, true)?.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(len: usize) -> TableHeader {
+        TableHeader {
+            coordinate: Coordinate::PartialEdges(vec![0, 1, 2]),
+            targets: "E:2o,2o".parse().unwrap(),
+            packing: Packing::Nibble,
+            depth_bound: None,
+            len,
+        }
+    }
+
+    fn stats(table: &PackedTable) -> TableStats {
+        TableStats::from_histogram(&table.histogram())
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let table = PackedTable::from_unpacked(&[0, 1, 2, 3, 2, 1, 255]);
+        let bytes = encode(&header(7), &table, &stats(&table));
+        assert!(has_magic(&bytes));
+        let (info, decoded) = decode(bytes, &header(7)).unwrap();
+        assert_eq!(decoded, table);
+        assert_eq!(info.header, header(7));
+        assert_eq!(info.histogram[..4], [1, 2, 2, 1]);
+        assert_eq!(info.histogram[15], 1);
+
+        let bounded = header(7).with_depth_bound(Some(2));
+        let bytes = encode(&bounded, &table, &stats(&table));
+        assert_eq!(decode(bytes.clone(), &bounded).unwrap().0.header, bounded);
+        assert!(matches!(
+            decode(bytes, &header(7)),
+            Err(TableFileError::Mismatch { .. })
+        ));
+
+        // 8 * 7 placements of the corners and 12 * 2 of the edge
+        let table = PackedTable::new(56 * 24, Packing::Nibble);
+        for target in [None, Some(5)] {
+            let pattern = TableHeader {
+                coordinate: Coordinate::Pattern {
+                    pattern: Pattern::new()
+                        .with_corners(&[2, 0], false)
+                        .with_edges(&[1], true),
+                    target,
+                },
+                ..header(56 * 24)
+            };
+            let bytes = encode(&pattern, &table, &stats(&table));
+            assert_eq!(decode(bytes, &pattern).unwrap().0.header, pattern);
+        }
+    }
+
+    #[test]
+    fn test_reads_version_1() {
+        let table = PackedTable::from_unpacked(&[0, 1, 2, 3, 2, 1, 1]);
+        let mut bytes = encode(&header(7), &table, &stats(&table));
+        // version 1 has neither the depth bound, which follows the
+        // coordinate (with its 3 edges) and the packing, nor the statistics,
+        // which follow the targets, entry count and histogram of 16 counts
+        let stats_start = 4 + 2 + 5 + 1 + 1 + 2 + "E:2o,2o".len() + 8 + 2 + 16 * 8;
+        let stats_len = 2 + 4 * 8 + 8;
+        bytes[4..6].copy_from_slice(&1_u16.to_le_bytes());
+        bytes.drain(stats_start..stats_start + stats_len);
+        bytes.remove(4 + 2 + 5 + 1);
+        bytes.truncate(bytes.len() - 4);
+        let checksum = crc32fast::hash(&bytes);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+        let (info, decoded) = decode(bytes, &header(7)).unwrap();
+        assert_eq!(info.header, header(7));
+        assert_eq!(info.stats, None);
+        assert_eq!(decoded, table);
+    }
+
+    #[test]
+    fn test_load() {
+        let table = PackedTable::from_unpacked(&[0, 1, 2, 3, 2, 1, 1]);
+        let path = std::env::temp_dir().join(format!("test_load_{}.pt", std::process::id()));
+        write(&path, &header(7), &table, &stats(&table)).unwrap();
+        let (info, loaded) = load(&path, &header(7)).unwrap();
+        assert_eq!(loaded, table);
+        assert_eq!(info.histogram, table.histogram());
+        assert_eq!(info.stats, Some(stats(&table)));
+        assert_eq!(loaded.is_mapped(), cfg!(feature = "mmap"));
+        assert_eq!(read_info(&path).unwrap().header, header(7));
+        assert_eq!(read_any(&path).unwrap(), (info, table));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_map_skips_checksum() {
+        let table = PackedTable::from_unpacked(&[0, 1, 2, 3, 2, 1, 1]);
+        let mut bytes = encode(&header(7), &table, &stats(&table));
+        let last_data = bytes.len() - 5;
+        bytes[last_data] ^= 1;
+        let path = std::env::temp_dir().join(format!("test_map_{}.pt", std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        assert!(map(&path, &header(7)).unwrap().1.is_mapped());
+        assert!(matches!(
+            read(&path, &header(7)),
+            Err(TableFileError::Checksum { .. })
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_refuses_bad_files() {
+        let table = PackedTable::from_unpacked(&[0, 1, 2, 3, 2, 1, 1]);
+        let bytes = encode(&header(7), &table, &stats(&table));
+
+        let truncated = bytes[..bytes.len() - 6].to_vec();
+        assert!(matches!(
+            decode(truncated, &header(7)),
+            Err(TableFileError::Truncated)
+        ));
+        let mut corrupted = bytes.clone();
+        let last_data = corrupted.len() - 5;
+        corrupted[last_data] ^= 1;
+        assert!(matches!(
+            decode(corrupted, &header(7)),
+            Err(TableFileError::Checksum { .. })
+        ));
+        let other = TableHeader {
+            targets: "E:2o,2n".parse().unwrap(),
+            ..header(7)
+        };
+        assert!(matches!(
+            decode(bytes.clone(), &other),
+            Err(TableFileError::Mismatch { .. })
+        ));
+        assert!(matches!(
+            decode(table.as_bytes().to_vec(), &header(7)),
+            Err(TableFileError::NotATable)
+        ));
+
+        // patterns are checked even when no header is expected
+        let pattern = |edges: &[u8], len| TableHeader {
+            coordinate: Coordinate::Pattern {
+                pattern: Pattern::new().with_edges(edges, false),
+                target: None,
+            },
+            ..header(len)
+        };
+        let decode_any = |header: &TableHeader| {
+            let table = PackedTable::new(header.len, Packing::Nibble);
+            decode_table(encode(header, &table, &stats(&table)), None)
+        };
+        assert!(decode_any(&pattern(&[3], 12)).is_ok());
+        for bad in [pattern(&[3, 3], 132), pattern(&[12], 12), pattern(&[3], 7)] {
+            assert!(matches!(
+                decode_any(&bad),
+                Err(TableFileError::Malformed(_))
+            ));
+        }
+    }
+}
@@ -1,7 +1,10 @@
 #[cfg(test)]
 mod tests {
     use rusty_rubik::cube::*;
+    use rusty_rubik::heuristic::Zero;
     use rusty_rubik::parser::*;
+    use rusty_rubik::solver::IDASolver;
+    use rusty_rubik::{CycleType, Targets};
     // PARSER TESTS
     #[test]
     fn parse_single_move() {
@@ -43,6 +46,82 @@ mod tests {
         )
     }
 
+    #[test]
+    fn notations_round_trip() {
+        let scramble = "R U' F2 D L' B2";
+        let seq = MoveSequence::from(parse_scramble(scramble).unwrap());
+        assert_eq!(seq.display(Notation::Singmaster).to_string(), scramble);
+        assert_eq!(
+            seq.display(Notation::Numeric).to_string(),
+            "R1 U3 F2 D1 L3 B2"
+        );
+        assert_eq!(seq.display(Notation::Compact).to_string(), "RU'F2DL'B2");
+        for notation in [
+            Notation::Singmaster,
+            Notation::Sign,
+            Notation::Numeric,
+            Notation::Compact,
+            Notation::Twsearch,
+        ] {
+            let written = seq.display(notation).to_string();
+            assert_eq!(
+                parse_scramble_with(&written, notation).unwrap(),
+                seq.to_vec()
+            );
+        }
+    }
+
+    #[test]
+    fn parse_rejects_other_notations() {
+        assert!(parse_scramble_with("R U'", Notation::Numeric).is_err());
+        assert!(parse_scramble_with("R1 U3", Notation::Singmaster).is_err());
+        assert!(parse_scramble_with("RX", Notation::Compact).is_err());
+        assert_eq!(
+            parse_scramble_with("R3 U2'", Notation::Twsearch).unwrap(),
+            parse_scramble("R' U2").unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_scrambles_from_reader() {
+        let input = "U2 F'\n\nR U R' U'\nR X\n";
+        let mut scrambles = parse_scrambles(input.as_bytes(), Notation::Singmaster);
+        assert_eq!(scrambles.next().unwrap().unwrap().len(), 2);
+        assert_eq!(scrambles.line_number(), 1);
+        assert_eq!(scrambles.next().unwrap().unwrap().len(), 4);
+        assert_eq!(scrambles.line_number(), 3);
+        match scrambles.next().unwrap() {
+            Err(ParseError::InvalidToken { line, token }) => {
+                assert_eq!(line, 4);
+                assert_eq!(token, "X");
+            }
+            _ => panic!("expected an invalid token error"),
+        }
+        assert!(scrambles.next().is_none());
+    }
+
+    #[test]
+    fn parse_scrambles_stops_after_read_error() {
+        struct Failing;
+        impl std::io::Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("unreadable"))
+            }
+        }
+        let mut scrambles = parse_scrambles(std::io::BufReader::new(Failing), Notation::Singmaster);
+        assert!(matches!(
+            scrambles.next(),
+            Some(Err(ParseError::Io { line: 1, .. }))
+        ));
+        assert!(scrambles.next().is_none());
+        assert_eq!(
+            parse_scramble_with("R X", Notation::Singmaster).unwrap_err(),
+            InvalidMove {
+                token: "X".to_string()
+            }
+        );
+    }
+
     // CUBE STRUCTURE TESTS
 
     #[test]
@@ -60,4 +139,102 @@ mod tests {
         // assert_eq!(eo, 0);
         // assert_eq!(ep, 0);
     }
+
+    // CYCLE TYPE TESTS
+
+    #[test]
+    fn cycle_type_round_trip() {
+        let cycle_type: CycleType<u8> = "C:1o,2n,3o E:2o,2o".parse().unwrap();
+        assert_eq!(
+            cycle_type,
+            CycleType {
+                corner_partition: vec![(1, true), (2, false), (3, true)],
+                edge_partition: vec![(2, true), (2, true)],
+            }
+        );
+        assert_eq!(cycle_type.to_string(), "C:1o,2n,3o E:2o,2o");
+        assert_eq!("E:2o".parse::<CycleType<u8>>().unwrap().to_string(), "E:2o");
+        assert_eq!(CycleType::<u8>::default().to_string(), "C:");
+        assert_eq!("C:".parse::<CycleType<u8>>().unwrap(), CycleType::default());
+    }
+
+    #[test]
+    fn cycle_type_canonical_order() {
+        let a: CycleType<u8> = "E:3n,1o C:3o,2n,2o".parse().unwrap();
+        let b: CycleType<u8> = "C:2o,2n,3o E:1o,3n".parse().unwrap();
+        assert_ne!(a, b);
+        assert_eq!(a.canonical(), b.canonical());
+        assert_eq!(a.to_string(), "C:2n,2o,3o E:1o,3n");
+    }
+
+    #[test]
+    fn cycle_type_parse_errors() {
+        assert!("".parse::<CycleType<u8>>().is_err());
+        assert!("C:1x".parse::<CycleType<u8>>().is_err());
+        assert!("C:o".parse::<CycleType<u8>>().is_err());
+        assert!("C:1o C:2n".parse::<CycleType<u8>>().is_err());
+        assert!("X:1o".parse::<CycleType<u8>>().is_err());
+    }
+
+    #[test]
+    fn cycle_type_order() {
+        let order = |cycle_type: &str| cycle_type.parse::<CycleType<u8>>().unwrap().order();
+        assert_eq!(order("C:"), 1);
+        assert_eq!(order("C:1o,7o"), 21);
+        assert_eq!(order("C:2n,3n E:4n"), 12);
+        assert_eq!(order("C:5o E:2o,3n"), 60);
+    }
+
+    // TARGETS TESTS
+
+    #[test]
+    fn targets_round_trip() {
+        let targets: Targets = "C:3o,1o | C:2n,2n | C:1o,3o".parse().unwrap();
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets.to_string(), "C:1o,3o | C:2n,2n");
+        assert_eq!(targets.to_string().parse::<Targets>().unwrap(), targets);
+        let single: CycleType<u8> = "C:1o,2n,3o E:2o,2o".parse().unwrap();
+        assert_eq!(Targets::from(&single).to_string(), single.to_string());
+        assert!("C:1o |".parse::<Targets>().is_err());
+    }
+
+    #[test]
+    fn targets_of_corners() {
+        let all = Targets::corners_where(|_| true);
+        assert!(all
+            .iter()
+            .all(|cycle_type| cycle_type.edge_partition.is_empty()));
+        assert!(all
+            .iter()
+            .any(|cycle_type| *cycle_type == CycleType::default()));
+        // a lone twisted corner and a lone swap are impossible
+        assert!(!all
+            .iter()
+            .any(|cycle_type| cycle_type.to_string() == "C:1o"));
+        assert!(!all
+            .iter()
+            .any(|cycle_type| cycle_type.to_string() == "C:2n"));
+        let high = Targets::corners_where(|cycle_type| cycle_type.order() >= 18);
+        assert!(high.iter().all(|cycle_type| cycle_type.order() >= 18));
+        assert_eq!(
+            high.to_string(),
+            "C:1o,2n,2n,3o | C:1o,2n,2o,3o | C:1o,2o,2o,3o | C:1o,7o | C:2n,2o,3o | C:2o,2o,3o \
+             | C:2o,6o | C:3o,5o"
+        );
+    }
+
+    #[test]
+    fn solve_towards_targets() {
+        // a quarter turn cycles four corners and four edges, and a half turn
+        // swaps two pairs of each
+        let quarter: CycleType<u8> = "C:4n E:4n".parse().unwrap();
+        let half: CycleType<u8> = "C:2n,2n E:2n,2n".parse().unwrap();
+        let solution = IDASolver::new(CubeState::default(), &Zero, half.clone()).solve();
+        assert_eq!(solution.len(), 1);
+        let targets = Targets::new([quarter.clone(), half]);
+        let solution = IDASolver::new(CubeState::default(), &Zero, targets).solve();
+        assert_eq!(solution.len(), 1);
+        let state = CubeState::default().apply_move_instances(&solution);
+        assert!(state.induces_cycle_type(&quarter, &mut [0; 12]));
+    }
 }